@@ -0,0 +1,173 @@
+use std::{env, fs, path::PathBuf, str::FromStr, time::Instant};
+
+use glam::Vec2;
+
+use perftest::phys_world::{
+    all_physics_manager::PhysicsManager, chunk::{COLS, ROWS, UPSCALE_FACTOR}, chunk_manager::EditInput, element_registry,
+    scenario::Scenario, weather::Precipitation, world_image::world_image, world_save,
+};
+
+const USAGE: &str = "Usage: sandsim [options]
+
+Steps a falling sand world without a window and dumps it as PNG frames.
+
+Options:
+    --scenario <name>   Built-in world to start from (default: sand_pile)
+    --load <file>       Start from a saved world instead of a scenario, the seed of the save is used
+    --save <file>       Save the world after the last tick
+    --elements <file>   Element definitions to use instead of the built-in ones
+    --ticks <n>         Number of fixed ticks to simulate, 0 writes the starting world (default: 300)
+    --every <n>         Also write a frame every n ticks (default: only the last tick)
+    --out <dir>         Directory the frames are written to (default: frames)
+    --seed <n>          World seed, the same seed always gives the same frames (default: 0)
+    --threads <n>       Maximum amount of threads used per update pass, 0 is unlimited (default: 0)
+    --weather <name>    Weather during the run: clear, rain or snow (default: clear)
+    --wind <x>          Sideways wind speed, negative blows to the left (default: 0)
+    --list              List the built-in scenarios
+    --help              Print this message";
+
+struct Options {
+    scenario: Scenario,
+    load: Option<PathBuf>,
+    save: Option<PathBuf>,
+    elements: Option<PathBuf>,
+    ticks: u32,
+    every: u32,
+    out: PathBuf,
+    seed: u64,
+    threads: usize,
+    weather: Precipitation,
+    wind: f32,
+}
+
+fn main() -> Result<(), String> {
+    let options = match parse_args(env::args().skip(1).collect())? {
+        Some(options) => options,
+        None => return Ok(()),
+    };
+
+    fs::create_dir_all(&options.out).map_err(|e| format!("Could not create {}: {}", options.out.display(), e))?;
+
+    if let Some(path) = &options.elements {
+        element_registry::load_registry(path)?;
+    }
+
+    let mut physics_manager = PhysicsManager::new();
+    physics_manager.chunk_manager.seed = options.seed;
+    physics_manager.chunk_manager.max_threads = options.threads;
+    physics_manager.weather.precipitation = options.weather;
+    physics_manager.weather.wind = Vec2::new(options.wind, 0.);
+    match &options.load {
+        Some(path) => world_save::load_world_from_file(&mut physics_manager, path)
+            .map_err(|e| format!("Could not load {}: {}", path.display(), e))?,
+        None => options.scenario.build(&mut physics_manager.chunk_manager),
+    }
+
+    // INFO: The starting chunks stay loaded and are what the frames show, the world can still grow around them
+    let (min, max) = physics_manager.chunk_manager.chunk_index.bounds().ok_or("The world has no chunks")?;
+    physics_manager.chunk_manager.view = Some((
+        (min.0 as f32 * COLS as f32 * UPSCALE_FACTOR, min.1 as f32 * ROWS as f32 * UPSCALE_FACTOR),
+        ((max.0 + 1) as f32 * COLS as f32 * UPSCALE_FACTOR - 1., (max.1 + 1) as f32 * ROWS as f32 * UPSCALE_FACTOR - 1.),
+    ));
+
+    let edit = EditInput::default();
+    let now = Instant::now();
+    // INFO: Without any ticks the frame is the starting world
+    if options.ticks == 0 {
+        write_frame(&physics_manager, &options, 0, (min, max))?;
+    }
+    for tick in 1..=options.ticks {
+        physics_manager.step(&edit);
+
+        if tick == options.ticks || (options.every != 0 && tick % options.every == 0) {
+            write_frame(&physics_manager, &options, tick, (min, max))?;
+        }
+
+        physics_manager.clear_body_bytes();
+    }
+
+    if let Some(path) = &options.save {
+        world_save::save_world_to_file(&physics_manager, path).map_err(|e| format!("Could not save {}: {}", path.display(), e))?;
+    }
+
+    let world_name = match &options.load {
+        Some(path) => path.display().to_string(),
+        None => options.scenario.name().to_string(),
+    };
+    println!(
+        "Simulated {} ticks of '{}' in {:?}, world checksum: {:016x}",
+        options.ticks,
+        world_name,
+        now.elapsed(),
+        physics_manager.chunk_manager.world_checksum()
+    );
+
+    Ok(())
+}
+
+fn write_frame(physics_manager: &PhysicsManager, options: &Options, tick: u32, region: ((i32, i32), (i32, i32))) -> Result<(), String> {
+    let path = options.out.join(format!("frame_{:05}.png", tick));
+    world_image(&physics_manager.chunk_manager.chunks, region.0, region.1)
+        .save(&path)
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+fn parse_args(args: Vec<String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        scenario: Scenario::SandPile,
+        load: None,
+        save: None,
+        elements: None,
+        ticks: 300,
+        every: 0,
+        out: PathBuf::from("frames"),
+        seed: 0,
+        threads: 0,
+        weather: Precipitation::Clear,
+        wind: 0.,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scenario" => {
+                let name = next_value(&mut args, &arg)?;
+                options.scenario = Scenario::from_name(&name).ok_or(format!("Unknown scenario '{}', see --list", name))?;
+            }
+            "--load" => options.load = Some(PathBuf::from(next_value(&mut args, &arg)?)),
+            "--save" => options.save = Some(PathBuf::from(next_value(&mut args, &arg)?)),
+            "--elements" => options.elements = Some(PathBuf::from(next_value(&mut args, &arg)?)),
+            "--ticks" => options.ticks = parse_number(&next_value(&mut args, &arg)?, &arg)?,
+            "--every" => options.every = parse_number(&next_value(&mut args, &arg)?, &arg)?,
+            "--out" => options.out = PathBuf::from(next_value(&mut args, &arg)?),
+            "--seed" => options.seed = parse_number(&next_value(&mut args, &arg)?, &arg)?,
+            "--threads" => options.threads = parse_number(&next_value(&mut args, &arg)?, &arg)?,
+            "--weather" => {
+                let name = next_value(&mut args, &arg)?;
+                options.weather = Precipitation::from_name(&name).ok_or(format!("Unknown weather '{}'", name))?;
+            }
+            "--wind" => options.wind = parse_number(&next_value(&mut args, &arg)?, &arg)?,
+            "--list" => {
+                for scenario in Scenario::ALL {
+                    println!("{}", scenario.name());
+                }
+                return Ok(None);
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            }
+            _ => return Err(format!("Unknown argument '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, arg: &str) -> Result<String, String> {
+    args.next().ok_or(format!("Missing value for {}", arg))
+}
+
+fn parse_number<T: FromStr>(value: &str, arg: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid number '{}' for {}", value, arg))
+}
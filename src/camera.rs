@@ -57,6 +57,15 @@ impl Camera2D {
         self.pos.y += y;
     }
 
+    // INFO: The smallest and largest world position the camera can see
+    pub fn view_rect(&self) -> ((f32, f32), (f32, f32)) {
+        let half_size = self.work_size / 2. / self.scale;
+        (
+            (self.pos.x - half_size.x, self.pos.y - half_size.y),
+            (self.pos.x + half_size.x, self.pos.y + half_size.y),
+        )
+    }
+
     pub fn set_position_to_center(&mut self) {
         self.set_position(self.work_size.x * 0.5, self.work_size. y * 0.5);
     }
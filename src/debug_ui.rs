@@ -7,7 +7,7 @@ use notan::{
     input::keyboard::KeyCode,
 };
 
-use crate::{phys_world::chunk_manager::ChunkManager, phys_world::{element::*, rapier_world_handler::{RapierHandler, SelectBody}, all_physics_manager::PhysicsManager}};
+use perftest::phys_world::{chunk_manager::ChunkManager, element::*, element_registry::registry, rapier_world_handler::{RapierHandler, SelectBody}, all_physics_manager::PhysicsManager, weather::{Precipitation, Weather}};
 
 pub struct DebugInfo {
     pub set_visuals: bool,
@@ -24,6 +24,7 @@ pub struct DebugInfo {
     pub debug_mem_usage: bool,
     pub sky_color: [u8; 3],
     pub sky_editor: bool,
+    pub weather_editor: bool,
 }
 
 impl Default for DebugInfo {
@@ -43,6 +44,7 @@ impl Default for DebugInfo {
             debug_mem_usage: false,
             sky_color: [70, 35, 70],
             sky_editor: false,
+            weather_editor: false,
         }
     }
 }
@@ -54,6 +56,9 @@ pub fn debug_update(app: &App, debug_info: &mut DebugInfo, physics_manager: &mut
     if app.keyboard.was_pressed(KeyCode::Y) {
         debug_info.sky_editor = !debug_info.sky_editor;
     }
+    if app.keyboard.was_pressed(KeyCode::G) {
+        debug_info.weather_editor = !debug_info.weather_editor;
+    }
     if app.keyboard.was_pressed(KeyCode::Space) {
         physics_manager.pause_all_phys = !physics_manager.pause_all_phys;
     }
@@ -90,6 +95,7 @@ pub fn debug_ui(
     debug_metrics(ctx, app, debug_info, &mut physics_manager.chunk_manager);
     debug_mem_usage(ctx, debug_info);
     debug_sky_editor(ctx, debug_info);
+    debug_weather_editor(ctx, debug_info, &mut physics_manager.weather);
     debug_rapier_window(ctx, debug_info, &mut physics_manager.rapier_handler);
 }
 
@@ -112,73 +118,17 @@ pub fn debug_editor(
             ui.add_space(5.);
 
             ui.horizontal_wrapped(|ui| {
-                if ui.button(RichText::new("Air").color(Color32::from_rgb(255, 255, 255))).clicked() {
-                    physics_manager.chunk_manager.selected_element = air_element();
-                }
-                if ui.button(RichText::new("Solid").color(Color32::from_rgb(169, 162, 166))).clicked() {
-                    physics_manager.chunk_manager.selected_element = solid_element();
-                }
-                if ui.button(RichText::new("Sand").color(Color32::from_rgb(243, 239, 118))).clicked() {
-                    physics_manager.chunk_manager.selected_element = sand_element();
-                }
-                if ui.button(RichText::new("Dirt").color(Color32::from_rgb(136, 107, 82))).clicked() {
-                    physics_manager.chunk_manager.selected_element = dirt_element();
-                }
-                if ui.button(RichText::new("Coal").color(Color32::from_rgb(130, 130, 130))).clicked() {
-                    physics_manager.chunk_manager.selected_element = coal_element();
-                }
-                if ui.button(RichText::new("Wood").color(Color32::from_rgb(111, 83, 57))).clicked() {
-                    physics_manager.chunk_manager.selected_element = wood_element();
-                }
-                if ui.button(RichText::new("SawDust").color(Color32::from_rgb(181, 137, 100))).clicked() {
-                    physics_manager.chunk_manager.selected_element = sawdust_element();
-                }
-                if ui.button(RichText::new("Water").color(Color32::from_rgb(75, 66, 249))).clicked() {
-                    physics_manager.chunk_manager.selected_element = water_element();
-                }
-                if ui.button(RichText::new("Smoke").color(Color32::from_rgb(142, 142, 142))).clicked() {
-                    physics_manager.chunk_manager.selected_element = smoke_element();
-                }
-                if ui.button(RichText::new("Steam").color(Color32::from_rgb(143, 159, 234))).clicked() {
-                    physics_manager.chunk_manager.selected_element = steam_element();
-                }
-                if ui.button(RichText::new("Petrol").color(Color32::from_rgb(0, 95, 106))).clicked() {
-                    physics_manager.chunk_manager.selected_element = petrol_element();
-                }
-                if ui.button(RichText::new("Methane").color(Color32::from_rgb(130, 171, 41))).clicked() {
-                    physics_manager.chunk_manager.selected_element = methane_element();
-                }
-                if ui.button(RichText::new("Fire").color(Color32::from_rgb(255, 0, 0))).clicked() {
-                    let mut element = fire_element();
-                    element.lifetime = 150;
-                    physics_manager.chunk_manager.selected_element = element;
-                }
-                if ui.button(RichText::new("Lava").color(Color32::from_rgb(234, 46, 56))).clicked() {
-                    physics_manager.chunk_manager.selected_element = lava_element();
-                }
-                if ui.button(RichText::new("Source").color(Color32::from_rgb(252, 186, 3))).clicked() {
-                    physics_manager.chunk_manager.selected_element = source_element();
-                }
-                if ui.button(RichText::new("Gravel").color(Color32::from_rgb(83, 84, 78))).clicked() {
-                    physics_manager.chunk_manager.selected_element = gravel_element();
-                }
-                if ui.button(RichText::new("Solid Dirt").color(Color32::from_rgb(136, 107, 82))).clicked() {
-                    physics_manager.chunk_manager.selected_element = soliddirt_element();
-                }
-                if ui.button(RichText::new("Grass").color(Color32::from_rgb(19, 109, 21))).clicked() {
-                    physics_manager.chunk_manager.selected_element = grass_element();
-                }
-                if ui.button(RichText::new("Brick").color(Color32::from_rgb(156, 89, 89))).clicked() {
-                    physics_manager.chunk_manager.selected_element = brick_element();
-                }
-                if ui.button(RichText::new("Snow").color(Color32::from_rgb(200, 200, 200))).clicked() {
-                    physics_manager.chunk_manager.selected_element = snow_element();
-                }
-                if ui.button(RichText::new("Ice").color(Color32::from_rgb(154, 176, 221))).clicked() {
-                    physics_manager.chunk_manager.selected_element = ice_element();
-                }
-                if ui.button(RichText::new("Fireworks").color(Color32::from_rgb(243, 46, 161))).clicked() {
-                    physics_manager.chunk_manager.selected_element = firework_shell_element();
+                for element in registry().editor_elements() {
+                    let Some(editor) = &registry().get(*element).editor else {
+                        continue;
+                    };
+                    if ui.button(RichText::new(&editor.label).color(Color32::from_rgb(editor.color.0, editor.color.1, editor.color.2))).clicked() {
+                        let mut cell = el_from_enum(*element);
+                        if let Some(lifetime) = editor.lifetime {
+                            cell.lifetime = lifetime;
+                        }
+                        physics_manager.chunk_manager.selected_element = cell;
+                    }
                 }
             });
             ui.add_space(5.);
@@ -196,9 +146,11 @@ pub fn debug_editor(
             }
 
             ui.label("Press Y to modify sky color");
+            ui.label("Press G to change the weather");
             ui.label("Press T for debug info");
             ui.label("Press M for metrics");
             ui.label("Press U for rapier2D info");
+            ui.label("Press F5 to save the world, F9 to load it");
         });
 }
 
@@ -249,6 +201,22 @@ pub fn debug_render(ctx: &Context, debug_info: &mut DebugInfo, chunk_manager: &m
                 "    lifetime: {:?}",
                 chunk_manager.hovering_cell.0.lifetime
             ));
+            ui.label(format!(
+                "    temperature: {:.1}",
+                chunk_manager.hovering_cell.0.temperature
+            ));
+            ui.label(format!(
+                "    charge: {}",
+                chunk_manager.hovering_cell.0.charge
+            ));
+            ui.label(format!(
+                "    wetness: {}",
+                chunk_manager.hovering_cell.0.wetness
+            ));
+            ui.label(format!(
+                "    free_falling: {}",
+                chunk_manager.hovering_cell.0.free_falling
+            ));
             ui.label("}");
         });
 }
@@ -295,6 +263,12 @@ pub fn debug_metrics(
                 debug_info.longest_update_time = Duration::ZERO;
             }
 
+            ui.label(format!(
+                "Loaded chunks: {}, unloaded to disk: {}",
+                chunk_manager.chunk_index.len(),
+                chunk_manager.stored_chunks.len()
+            ));
+
             ui.add_space(5.);
 
             for i in 0..chunk_manager.num_of_threads.len() {
@@ -359,6 +333,28 @@ pub fn debug_sky_editor(ctx: &Context, debug_info: &mut DebugInfo) {
         });
 }
 
+pub fn debug_weather_editor(ctx: &Context, debug_info: &mut DebugInfo, weather: &mut Weather) {
+    Window::new("Weather")
+        .resizable(false)
+        .collapsible(true)
+        .open(&mut debug_info.weather_editor)
+        .show(ctx, |ui| {
+            ComboBox::from_label("Precipitation").selected_text(weather.precipitation.name()).show_ui(ui, |ui| {
+                for precipitation in Precipitation::ALL {
+                    ui.selectable_value(&mut weather.precipitation, precipitation, precipitation.name());
+                }
+            });
+            ui.add(Slider::new(&mut weather.rate, 0.0..=0.1).clamp_to_range(true).prefix("rate: "));
+            ui.add(Slider::new(&mut weather.wind.x, -5.0..=5.0).clamp_to_range(true).prefix("wind x: "));
+            ui.add(Slider::new(&mut weather.wind.y, -5.0..=5.0).clamp_to_range(true).prefix("wind y: "));
+
+            ui.add_space(5.);
+
+            ui.checkbox(&mut weather.changes, "Changes over time");
+            ui.add(Slider::new(&mut weather.change_interval, 60..=20000).clamp_to_range(true).prefix("every: ").suffix(" frames"));
+        });
+}
+
 pub fn debug_rapier_window(ctx: &Context, debug_info: &mut DebugInfo, rapier_handler: &mut RapierHandler) {
     Window::new("Rapier2D").resizable(false).collapsible(true).open(&mut debug_info.debug_rapier2d).show(ctx, |ui| {
         ui.checkbox(&mut rapier_handler.update_phys, "Update rapier physics");
@@ -1,4 +1,7 @@
 use notan::prelude::{App, KeyCode};
+use std::path::Path;
+
+use perftest::phys_world::{all_physics_manager::PhysicsManager, chunk_manager::EditInput, rapier_world_handler::SelectBody, world_save};
 
 use crate::camera::Camera2D;
 
@@ -29,6 +32,70 @@ pub fn camera_control(app: &mut App, camera: &mut Camera2D, camera_zoom: &mut f3
     }
 }
 
+pub fn brush_control(app: &App, brush_size: &mut i32) {
+    if app.mouse.is_scrolling() {
+        *brush_size += app.mouse.wheel_delta.y as i32 / 6;
+
+        if *brush_size <= 0 {
+            *brush_size = 1;
+        }
+    }
+}
+
+pub fn edit_input(app: &mut App, camera: &Camera2D) -> EditInput {
+    let mouse_world = get_mouse_in_world(
+        &(app.mouse.x, app.mouse.y),
+        (app.window().width() as i32, app.window().height() as i32),
+        camera,
+    );
+
+    EditInput {
+        mouse_world,
+        place: app.mouse.left_is_down(),
+        explode_force: if app.mouse.right_is_down() {
+            Some(4. * app.timer.delta_f32() * 90.)
+        } else {
+            None
+        },
+    }
+}
+
+const SAVE_PATH: &str = "world.sav";
+
+pub fn save_load_control(app: &App, physics_manager: &mut PhysicsManager) {
+    if app.keyboard.was_pressed(KeyCode::F5) {
+        if let Err(e) = world_save::save_world_to_file(physics_manager, Path::new(SAVE_PATH)) {
+            eprintln!("Could not save the world to {}: {}", SAVE_PATH, e);
+        }
+    }
+    if app.keyboard.was_pressed(KeyCode::F9) {
+        if let Err(e) = world_save::load_world_from_file(physics_manager, Path::new(SAVE_PATH)) {
+            eprintln!("Could not load the world from {}: {}", SAVE_PATH, e);
+        }
+    }
+}
+
+pub fn body_spawn_control(app: &mut App, camera: &Camera2D, physics_manager: &mut PhysicsManager) {
+    if !app.mouse.middle_was_released() {
+        return;
+    }
+
+    let mouse_world = get_mouse_in_world(
+        &(app.mouse.x, app.mouse.y),
+        (app.window().width() as i32, app.window().height() as i32),
+        camera,
+    );
+
+    let rapier_handler = &mut physics_manager.rapier_handler;
+    let tex_handler = &physics_manager.chunk_manager.tex_handler;
+    match rapier_handler.select_body {
+        SelectBody::Ball => rapier_handler.add_ball(mouse_world),
+        SelectBody::SandBodyBall => rapier_handler.add_sand_body(mouse_world, tex_handler, SelectBody::SandBodyBall),
+        SelectBody::SandBodySquare => rapier_handler.add_sand_body(mouse_world, tex_handler, SelectBody::SandBodySquare),
+        SelectBody::SandBodyRectangle => rapier_handler.add_sand_body(mouse_world, tex_handler, SelectBody::SandBodyRectangle),
+    }
+}
+
 pub fn get_mouse_in_world(mouse_pos: &(f32, f32), window_size: (i32, i32), camera: &Camera2D) -> (f32, f32) {
     let mouse_x = map(mouse_pos.0, 0.0, window_size.0 as f32, 0.0, camera.work_size.x / camera.scale().x);
     let mouse_y = map(mouse_pos.1, 0.0, window_size.1 as f32, 0.0, camera.work_size.y / camera.scale().y);
@@ -0,0 +1 @@
+pub mod phys_world;
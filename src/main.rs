@@ -1,7 +1,7 @@
-pub mod camera;
+mod camera;
 mod debug_ui;
-pub mod input_manager;
-pub mod phys_world;
+mod input_manager;
+mod render;
 
 use camera::Camera2D;
 use debug_ui::DebugInfo;
@@ -9,8 +9,10 @@ use input_manager::map;
 use notan::draw::*;
 use notan::egui::{EguiConfig, EguiPluginSugar};
 use notan::prelude::*;
-use phys_world::all_physics_manager::PhysicsManager;
-use phys_world::chunk::{COLS, ROWS, UPSCALE_FACTOR};
+use perftest::phys_world::all_physics_manager::PhysicsManager;
+use perftest::phys_world::chunk::{COLS, ROWS, UPSCALE_FACTOR};
+use perftest::phys_world::element_registry;
+use render::WorldRenderer;
 
 #[derive(AppState)]
 struct State {
@@ -18,11 +20,18 @@ struct State {
     camera_zoom: f32,
     sky_gradient: Texture,
     debug_info: DebugInfo,
-    physics_manager: PhysicsManager
+    physics_manager: PhysicsManager,
+    world_renderer: WorldRenderer
 }
 
 #[notan_main]
 fn main() -> Result<(), String> {
+    // INFO: Element definitions next to the game replace the built-in ones
+    let definitions = std::path::Path::new("elements.ron");
+    if definitions.exists() {
+        element_registry::load_registry(definitions)?;
+    }
+
     notan::init_with(init)
         .add_config(
             WindowConfig::new()
@@ -65,12 +74,18 @@ fn init(app: &mut App, gfx: &mut Graphics) -> State {
             .build()
             .unwrap(),
         debug_info: DebugInfo::default(),
-        physics_manager: PhysicsManager::new(gfx)
+        physics_manager: PhysicsManager::new(),
+        world_renderer: WorldRenderer::new(gfx)
     }
 }
 
 fn update(app: &mut App, state: &mut State) {
-    state.physics_manager.update(app, &state.camera);
+    input_manager::brush_control(app, &mut state.physics_manager.chunk_manager.brush_size);
+    let edit = input_manager::edit_input(app, &state.camera);
+    state.physics_manager.chunk_manager.view = Some(state.camera.view_rect());
+    state.physics_manager.update(app.timer.delta_f32(), &edit);
+    input_manager::body_spawn_control(app, &state.camera, &mut state.physics_manager);
+    input_manager::save_load_control(app, &mut state.physics_manager);
     
     if app.keyboard.was_pressed(KeyCode::Escape) {
         app.exit();
@@ -96,7 +111,7 @@ fn draw(app: &mut App, gfx: &mut Graphics, plugins: &mut Plugins, state: &mut St
 
     state.camera.apply(&mut render_draw);
 
-    state.physics_manager.render(app, gfx, &mut render_draw, &state.debug_info, &state.camera);
+    state.world_renderer.render(gfx, &mut render_draw, &mut state.physics_manager, &state.debug_info);
 
     render_draw.transform().pop();
 
@@ -1,8 +1,6 @@
-use notan::{prelude::{Graphics, App}, draw::Draw, math::{Mat3, Vec2, Vec3}};
+use glam::{Mat3, Vec2, Vec3};
 
-use crate::{camera::Camera2D, debug_ui::DebugInfo, input_manager::get_mouse_in_world};
-
-use super::{chunk_manager::ChunkManager, rapier_world_handler::{RapierHandler, PHYS_SCALE, SelectBody}, chunk::{UPSCALE_FACTOR, ROWS, COLS, self}, element::{air_element, Cell}, rigid_sand_body::ElInWorldInfo};
+use super::{chunk_manager::{ChunkManager, EditInput}, rapier_world_handler::{RapierHandler, PHYS_SCALE}, chunk::{UPSCALE_FACTOR, ROWS, COLS, self}, element::{air_element, Cell}, rigid_sand_body::{ElInWorldInfo, RigidSandBody}, support::{self, SupportCheck}, weather::Weather};
 
 const PHYSICS_UPDATE_DELTA: f64 = 0.016; // INFO: The physics sims update at 60 FPS
 
@@ -12,34 +10,43 @@ pub struct PhysicsManager {
     pub update_time: f64,
 	pub pause_all_phys: bool,
 	pub next_step: bool,
-	pub bodies_placed_paused: bool
+	pub bodies_placed_paused: bool,
+	pub weather: Weather,
+	pub support: SupportCheck
+}
+
+impl Default for PhysicsManager {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl PhysicsManager {
-	pub fn new(gfx: &mut Graphics) -> Self {
+	pub fn new() -> Self {
 		Self {
-			chunk_manager: ChunkManager::new(gfx),
+			chunk_manager: ChunkManager::new(),
 			rapier_handler: RapierHandler::new(),
             update_time: 0.,
 			pause_all_phys: false,
 			next_step: false,
-			bodies_placed_paused: false
+			bodies_placed_paused: false,
+			weather: Weather::new(),
+			support: SupportCheck::new()
 		}
 	}
 
-	pub fn update(&mut self, app: &mut App, camera: &Camera2D) {
-		self.chunk_manager.update_brush(app);
+	pub fn update(&mut self, delta: f32, edit: &EditInput) {
 		if self.pause_all_phys && !self.next_step {
+			self.stream_chunks();
 			self.rsbodies_to_chunks();
-			self.chunk_manager.update_chunk_edit(app, camera);
+			self.chunk_manager.update_chunk_edit(edit);
 			self.retrieve_els_to_rsbodies();
 		}
-		self.rapier_handler.update(app, camera);
 
 		// INFO: Update the physics simulations at 60 FPS
 
 		if self.next_step || !self.pause_all_phys {
-	        self.update_time += app.timer.delta_f32() as f64;
+	        self.update_time += delta as f64;
 	        if self.update_time >= PHYSICS_UPDATE_DELTA || self.next_step {
 	            self.update_time = 0.;
 
@@ -47,14 +54,7 @@ impl PhysicsManager {
 					return;
 				}
 
-		
-				self.rsbodies_to_chunks();
-				self.chunk_manager.update_chunk_edit(app, camera);
-				self.chunk_manager.update_chunks_fixed();
-				self.retrieve_els_to_rsbodies();
-			
-				self.rapier_handler.create_chunk_colliders(&mut self.chunk_manager.chunks);
-				self.rapier_handler.update_fixed();
+				self.step(edit);
 
 				if self.pause_all_phys {
 					self.next_step = false;
@@ -63,34 +63,73 @@ impl PhysicsManager {
 		}
 	}
 
-	pub fn render(&mut self, app: &mut App, gfx: &mut Graphics, render_draw: &mut Draw, debug_info: &DebugInfo, camera: &Camera2D) {
-	    self.chunk_manager.render(gfx, render_draw);
-		
-		for rsbody_index in 0..self.rapier_handler.rigid_sand_bodies.len() {
-			let rsbody = &mut self.rapier_handler.rigid_sand_bodies[rsbody_index];
+	// INFO: A single fixed physics tick, this is what headless runs call directly
+	pub fn step(&mut self, edit: &EditInput) {
+		self.stream_chunks();
+		self.rsbodies_to_chunks();
+		self.chunk_manager.update_chunk_edit(edit);
+		if self.chunk_manager.update_chunks {
+			self.weather.update(&mut self.chunk_manager);
+		}
+		self.chunk_manager.update_chunks_fixed();
+
+		// INFO: Searches while the rigid sand bodies are still in the chunks, structures can rest on them.
+		// It runs before the colliders are rebuilt, they are only dirty in the chunks where something solid changed
+		let unsupported = if self.chunk_manager.update_chunks {
+			self.support.unsupported_structures(&self.chunk_manager.chunks)
+		} else {
+			vec![]
+		};
+		self.retrieve_els_to_rsbodies();
+		self.detach_unsupported(unsupported);
+
+		for explosion in self.chunk_manager.take_explosions() {
+			self.rapier_handler.apply_explosion(&explosion);
+		}
+
+		self.rapier_handler.create_chunk_colliders(&mut self.chunk_manager.chunks);
+		self.rapier_handler.update_fixed();
+	}
+
+	// INFO: Rigid sand bodies keep the chunks around them loaded, unloaded chunks lose their colliders
+	fn stream_chunks(&mut self) {
+		let mut keep = vec![];
+		for rsbody in &self.rapier_handler.rigid_sand_bodies {
+			let pos = self.rapier_handler.rigid_body_set[rsbody.rigid_body_handle].translation();
+			keep.push(chunk::world_to_chunk_index((pos.x * PHYS_SCALE, pos.y * PHYS_SCALE)));
+		}
+
+		for index in self.chunk_manager.stream_chunks(&keep) {
+			self.rapier_handler.remove_chunk_colliders(index);
+		}
+	}
+
+	/*
+		INFO: Rigid sand body elements are only written into the chunk bytes for the duration of a tick,
+		so after the bytes were consumed (uploaded to a texture or dumped to an image) we restore them from the grid
+	*/
+	pub fn clear_body_bytes(&mut self) {
+		for rsbody in &self.rapier_handler.rigid_sand_bodies {
 			for el_info in &rsbody.body_elements_in_chunks {
 				if let Some(chunk) = self.chunk_manager.chunks.get_mut(&el_info.chunk) {
 					chunk::update_byte(&mut chunk.bytes, el_info.index_chunk.0, el_info.index_chunk.1, &chunk.grid[el_info.index_chunk.0][el_info.index_chunk.1].color);
 				}
 			}
 		}
+	}
 
-	    self.chunk_manager.debug_render(render_draw, debug_info);
-		self.rapier_handler.debug_render(render_draw, debug_info);
-
-		// TODO:  This is not a good place for this
-        let mouse_world = get_mouse_in_world(
-            &(app.mouse.x, app.mouse.y),
-            (app.window().width() as i32, app.window().height() as i32),
-            camera,
-        );
-		if app.mouse.middle_was_released() {
-			match self.rapier_handler.select_body {
-			    SelectBody::Ball => self.rapier_handler.add_ball(mouse_world),
-			    SelectBody::SandBodyBall => self.rapier_handler.add_sand_body(mouse_world, &self.chunk_manager.tex_handler, SelectBody::SandBodyBall),
-			    SelectBody::SandBodySquare => self.rapier_handler.add_sand_body(mouse_world, &self.chunk_manager.tex_handler, SelectBody::SandBodySquare),
-			    SelectBody::SandBodyRectangle => self.rapier_handler.add_sand_body(mouse_world, &self.chunk_manager.tex_handler, SelectBody::SandBodyRectangle),
+	// INFO: Structures that lost their support fall, big ones are lifted out of the chunks as rigid sand bodies and small ones crumble
+	fn detach_unsupported(&mut self, unsupported: Vec<Vec<(i32, i32)>>) {
+		for structure in unsupported {
+			if !support::becomes_body(&structure) {
+				support::collapse(&mut self.chunk_manager.chunks, &structure, &self.chunk_manager.tex_handler);
+				continue;
 			}
+
+			let (body_elements, position) = support::lift_out(&mut self.chunk_manager.chunks, &structure);
+			let rapier_handler = &mut self.rapier_handler;
+			let rsbody = RigidSandBody::from_elements(position.x, position.y, body_elements, &mut rapier_handler.rigid_body_set, &mut rapier_handler.collider_set);
+			rapier_handler.rigid_sand_bodies.push(rsbody);
 		}
 	}
 
@@ -118,8 +157,8 @@ impl PhysicsManager {
 				Vec3::new(rsbody.body_elements.len() as f32 * UPSCALE_FACTOR / 2., rsbody.body_elements[0].len() as f32 * UPSCALE_FACTOR / 2., 1.);
 
 			
-			for i in 0..body_els_rotated.len() {
-				for j in 0..body_els_rotated[0].len() {
+			for (i, column) in body_els_rotated.iter().enumerate() {
+				for (j, rotated_cell) in column.iter().enumerate() {
 
 					// INFO: Element coordinates in world space
 					let el_world = (
@@ -141,7 +180,7 @@ impl PhysicsManager {
 					    ((el_world.1 as f32 - el_chunk_y as f32 * ROWS as f32 * UPSCALE_FACTOR) / UPSCALE_FACTOR).floor() as i32 % ROWS as i32;
 
 					if let Some(chunk) = self.chunk_manager.chunks.get_mut(&(el_chunk_x, el_chunk_y)) {
-						if let Some(element) = body_els_rotated[i][j] {
+						if let Some(element) = *rotated_cell {
 							chunk.grid[cell_index_x as usize][cell_index_y as usize] = element.0;
 							chunk::update_byte(&mut chunk.bytes, cell_index_x as usize, cell_index_y as usize, &chunk.grid[cell_index_x as usize][cell_index_y as usize].color);
 
@@ -179,7 +218,10 @@ impl PhysicsManager {
 	}
 }
 
-fn rotate_arbitrary(body_elements: &Vec<Vec<Option<Cell>>>, angle_radians: f32) -> (Vec<Vec<Option<(Cell, usize, usize)>>>, i32, i32) {
+// INFO: A cell of a rotated body and its row and column in the body
+type RotatedCell = Option<(Cell, usize, usize)>;
+
+fn rotate_arbitrary(body_elements: &[Vec<Option<Cell>>], angle_radians: f32) -> (Vec<Vec<RotatedCell>>, i32, i32) {
     let height = body_elements.len() as f32;
     let width = body_elements[0].len() as f32;
     let cos_angle = angle_radians.cos();
@@ -196,8 +238,8 @@ fn rotate_arbitrary(body_elements: &Vec<Vec<Option<Cell>>>, angle_radians: f32)
     let new_x_center = new_width as f32 / 2.0;
     let new_y_center = new_height as f32 / 2.0;
 
-    for y in 0..new_height {
-        for x in 0..new_width {
+    for (y, row) in rotated.iter_mut().enumerate() {
+        for (x, rotated_cell) in row.iter_mut().enumerate() {
             let new_x = x as f32 - new_x_center;
             let new_y = y as f32 - new_y_center;
 
@@ -209,7 +251,7 @@ fn rotate_arbitrary(body_elements: &Vec<Vec<Option<Cell>>>, angle_radians: f32)
                 let old_y_int = old_y.floor() as usize;
 
 				if let Some(element) = body_elements[old_y_int][old_x_int] {
-	                rotated[y][x] = Some((element, old_y_int, old_x_int));
+	                *rotated_cell = Some((element, old_y_int, old_x_int));
 				}
             }
         }
@@ -1,8 +1,6 @@
-use notan::math::Vec2;
+use glam::Vec2;
 
-use crate::{phys_world::element::{Cell, State, solid_element}, phys_world::chunk::{ROWS, COLS, in_bound, self, Grid, MovData}, phys_world::chunk_manager::WorldChunks};
-
-use super::{element::Action, element_actions::{is_flammable, set_action}};
+use crate::{phys_world::element::{Cell, Element, State, solid_element}, phys_world::chunk::{ROWS, COLS, in_bound, self, Grid, MovData}, phys_world::element_registry::registry};
 
 // INFO: We set a max velocity so that elements wouldn't be able to jump over chunks
 pub const fn max_vel() -> f32 {
@@ -22,17 +20,28 @@ pub fn downward(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) ->
 	false
 }
 
+// INFO: The most water cells that make a wet powder stickier, wetter powders do not pile up any steeper
+const MAX_STICKINESS: i32 = 2;
+
 #[inline]
 pub fn downward_sides(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
 	let d = f_grid[i][j].density;
 
-	let mut left = get(i as i32 - 1, j as i32 + 1, f_grid, mov_dt).density < d
-					&& get(i as i32 - 1, j as i32 + 1, f_grid, mov_dt).state != State::Solid;
-	let mut right = get(i as i32 + 1, j as i32 + 1, f_grid, mov_dt).density < d
-					&& get(i as i32 + 1, j as i32 + 1, f_grid, mov_dt).state != State::Solid;
+	/*
+		INFO: Wet powders stick together, they only slide down a side that drops by more than one cell for every water cell they hold,
+		so they pile up steeper than dry ones
+	*/
+	let drop = 1 + (f_grid[i][j].wetness as i32).min(MAX_STICKINESS);
+	let mut slides = |dx: i32| (1..=drop).all(|dy| {
+		let side = get(i as i32 + dx, j as i32 + dy, f_grid, mov_dt);
+		side.density < d && side.state != State::Solid
+	});
+
+	let mut left = slides(-1);
+	let mut right = slides(1);
 	
 	if left && right {
-		let rand = fastrand::bool();
+		let rand = mov_dt.rng.bool();
 		left = rand;
 		right = !rand;
 	}
@@ -48,21 +57,24 @@ pub fn downward_sides(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovDat
 
 #[inline]
 pub fn apply_velocity(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
-	let dist = f_grid[i][j].velocity.length();
-
-	if dist < 0.5 {
-		// return false;
-	}
+	let blown = blow(f_grid, i, j, mov_dt);
 
 	// INFO: Clamp the elements speed to the maximum velocity
 	f_grid[i][j].velocity.x = f_grid[i][j].velocity.x.clamp(-max_vel(), max_vel());
 	f_grid[i][j].velocity.y = f_grid[i][j].velocity.y.clamp(-max_vel(), max_vel());
 
-	// INFO: We do this only for Powder elements, so other States could have slower accelarations
-	if f_grid[i][j].state == State::Powder {
-		if f_grid[i][j].velocity.x.abs() < 1.0 {
-			f_grid[i][j].velocity.x = 0.;
-		}
+	/*
+		INFO: The distance has to be taken after clamping, otherwise an element could travel further than max_vel
+		and reach into cells that a chunk updated in the same pass is also touching
+	*/
+	let dist = f_grid[i][j].velocity.length();
+
+	/*
+		INFO: We do this only for Powder elements, so other States could have slower accelarations. Powders in the wind keep their small speeds so they drift.
+		Wet powders have more friction, every water cell they hold stops faster sideways speeds
+	*/
+	if f_grid[i][j].state == State::Powder && !blown && f_grid[i][j].velocity.x.abs() < 1.0 + f_grid[i][j].wetness as f32 {
+		f_grid[i][j].velocity.x = 0.;
 	}
 
 	let (force_x, force_y) = (f_grid[i][j].velocity.x / dist, f_grid[i][j].velocity.y / dist);
@@ -115,6 +127,36 @@ pub fn apply_velocity(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovDat
 	false
 }
 
+/*
+	INFO: The wind pulls the sideways speed of gases and of powders and liquids that are falling towards its own and pushes them up or down.
+	Light cells follow it faster than heavy ones, everything resting on something is out of the wind
+*/
+#[inline]
+fn blow(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
+	const WIND_PULL: f32 = 0.2;
+	// INFO: Cells up to this density follow the wind the fastest
+	const WIND_DENSITY: f32 = 10.;
+
+	if mov_dt.wind == Vec2::ZERO {
+		return false;
+	}
+
+	let cell = f_grid[i][j];
+	let in_air = match cell.state {
+		State::Gas => true,
+		State::Powder | State::Liquid => get(i as i32, j as i32 + 1, f_grid, mov_dt).density < cell.density,
+		_ => false,
+	};
+	if !in_air {
+		return false;
+	}
+
+	let pull = WIND_PULL * WIND_DENSITY / cell.density.max(WIND_DENSITY);
+	f_grid[i][j].velocity.x += (mov_dt.wind.x - cell.velocity.x) * pull;
+	f_grid[i][j].velocity.y += mov_dt.wind.y * pull;
+	true
+}
+
 #[inline]
 pub fn apply_gravity(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
 	let below_element = get(i as i32, j as i32 + 1, future_grid, mov_dt);
@@ -129,18 +171,20 @@ pub fn apply_gravity(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut Mo
 			future_grid[i][j].velocity.y += g;
 		}
 	} else {
+		// INFO: Landing elements turn their fall into a sideways speed, friction takes away part of it
 		if below_element.velocity.y.abs() < 0.5 {
+			let slide = future_grid[i][j].velocity.y / 3. * (1. - registry().get(future_grid[i][j].element).friction);
 			if future_grid[i][j].velocity.x == 0. {
-				if fastrand::bool() {
-					future_grid[i][j].velocity.x += future_grid[i][j].velocity.y / 3.;
+				if mov_dt.rng.bool() {
+					future_grid[i][j].velocity.x += slide;
 				} else {
-					future_grid[i][j].velocity.x -= future_grid[i][j].velocity.y / 3.;
+					future_grid[i][j].velocity.x -= slide;
 				}
 			} else {
 				if future_grid[i][j].velocity.x < 0. {
-					future_grid[i][j].velocity.x -= (future_grid[i][j].velocity.y / 3.).abs();
+					future_grid[i][j].velocity.x -= slide.abs();
 				} else {
-					future_grid[i][j].velocity.x += (future_grid[i][j].velocity.y / 3.).abs();
+					future_grid[i][j].velocity.x += slide.abs();
 				}
 			}
 			future_grid[i][j].velocity.y = 0.;
@@ -148,23 +192,6 @@ pub fn apply_gravity(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut Mo
 	}
 }
 
-#[inline]
-pub fn spread_fire(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
-	if is_flammable(&get(i as i32, j as i32 - 1, f_grid, mov_dt)) {
-		set_action(i as i32, j as i32 - 1, f_grid, mov_dt, Some(Action::Burn));
-	}
-	if is_flammable(&get(i as i32, j as i32 + 1, f_grid, mov_dt)) {
-		set_action(i as i32, j as i32 + 1, f_grid, mov_dt, Some(Action::Burn));
-	}
-	if is_flammable(&get(i as i32 - 1, j as i32, f_grid, mov_dt)) {
-		set_action(i as i32 - 1, j as i32, f_grid, mov_dt, Some(Action::Burn));
-	}
-	if is_flammable(&get(i as i32 + 1, j as i32, f_grid, mov_dt)) {
-		set_action(i as i32 + 1, j as i32, f_grid, mov_dt, Some(Action::Burn));
-	}
-}
-
-
 #[inline]
 pub fn get(i: i32, j: i32, f_grid: &mut Grid, mov_dt: &mut MovData) -> Cell {
 	if in_bound(i, j) {
@@ -172,17 +199,29 @@ pub fn get(i: i32, j: i32, f_grid: &mut Grid, mov_dt: &mut MovData) -> Cell {
 	} else {
 		let wanted_chunk = get_wanted_chunk(mov_dt.index, i, j);
 
-		match mov_dt.chunks.get(&wanted_chunk) {
-			Some(chunk) => {
-				let (x, y) = get_new_element_coord(i, j);
-				return chunk.grid[x as usize][y as usize];
-			},
-			_ => ()
+		if let Some(chunk) = mov_dt.chunks.get(&wanted_chunk) {
+			let (x, y) = get_new_element_coord(i, j);
+			return chunk.grid[x as usize][y as usize];
 		}
 	}
 	solid_element()
 }
 
+// INFO: Same as get, only the element without copying the whole cell
+#[inline]
+pub fn get_element(i: i32, j: i32, f_grid: &Grid, mov_dt: &MovData) -> Element {
+	if in_bound(i, j) {
+		return f_grid[i as usize][j as usize].element
+	}
+	match mov_dt.chunks.get(&get_wanted_chunk(mov_dt.index, i, j)) {
+		Some(chunk) => {
+			let (x, y) = get_new_element_coord(i, j);
+			chunk.grid[x as usize][y as usize].element
+		},
+		None => Element::Solid
+	}
+}
+
 #[inline]
 pub fn set(i: i32, j: i32, f_grid: &mut Grid, mov_dt: &mut MovData, cell: Cell) {
 	if in_bound(i, j) {
@@ -191,28 +230,29 @@ pub fn set(i: i32, j: i32, f_grid: &mut Grid, mov_dt: &mut MovData, cell: Cell)
 		*mov_dt.keep_active = true;
 		mov_dt.dirty_rect.set_temp(i as usize, j as usize);
 		
-        chunk::update_byte(&mut mov_dt.bytes, i as usize, j as usize, &f_grid[i as usize][j as usize].color);
+        chunk::update_byte(mov_dt.bytes, i as usize, j as usize, &f_grid[i as usize][j as usize].color);
 	} else {
 		let wanted_chunk = get_wanted_chunk(mov_dt.index, i, j);
 
-		match mov_dt.chunks.get_mut(&wanted_chunk) {
-			Some(chunk) => {
-				let (x, y) = get_new_element_coord(i, j);
-				chunk.grid[x as usize][y as usize] = cell;
-		        chunk::update_byte(&mut chunk.bytes, x as usize, y as usize, &chunk.grid[x as usize][y as usize].color);
+		if let Some(chunk) = mov_dt.chunks.get_mut(&wanted_chunk) {
+			let (x, y) = get_new_element_coord(i, j);
+			chunk.grid[x as usize][y as usize] = cell;
+	        chunk::update_byte(&mut chunk.bytes, x as usize, y as usize, &chunk.grid[x as usize][y as usize].color);
 
-				if !chunk.active {
-					chunk::activate(chunk);
-					chunk.dirty_rect.set_temp(x as usize, y as usize);
-				} else {
-					chunk.dirty_rect.set_temp(x as usize, y as usize);
-				}
-			},
-			_ => ()
+			mov_dt.wake_ups.push((wanted_chunk, (x as usize, y as usize)));
 		}
 	}
 }
 
+// INFO: The chunk of the cell has to rebuild its colliders, for cells with a chunk collider that got removed or replaced
+pub fn set_colliders_dirty(i: i32, j: i32, mov_dt: &mut MovData) {
+	if in_bound(i, j) {
+		*mov_dt.colliders_dirty = true;
+	} else if let Some(chunk) = mov_dt.chunks.get_mut(&get_wanted_chunk(mov_dt.index, i, j)) {
+		chunk.colliders_dirty = true;
+	}
+}
+
 #[inline]
 pub fn swap(grid: &mut Grid, i1: usize, j1: usize, i2: i32, j2: i32, mov_dt: &mut MovData) -> bool {
 	if in_bound(i2, j2) { // INFO: Element swap happening inside of the chunk
@@ -227,18 +267,18 @@ pub fn swap(grid: &mut Grid, i1: usize, j1: usize, i2: i32, j2: i32, mov_dt: &mu
 
 		// INFO: Wake up neighboring sleeping chunks if chunk edge element moves
 		if i1 == 0 || i2 == 0 {
-			wake_up_chunk(mov_dt.chunks, mov_dt.index, (-1, 0), (COLS - 1, j1));
+			wake_up_chunk(mov_dt, (-1, 0), (COLS - 1, j1));
 		} else if i1 == COLS - 1 || i2 == COLS as i32 - 1 {
-			wake_up_chunk(mov_dt.chunks, mov_dt.index, (1, 0), (0, j1));
+			wake_up_chunk(mov_dt, (1, 0), (0, j1));
 		}
 
 		if j1 == 0 || j2 == 0 {
-			wake_up_chunk(mov_dt.chunks, mov_dt.index, (0, -1), (i1, ROWS - 1))
+			wake_up_chunk(mov_dt, (0, -1), (i1, ROWS - 1))
 		} else if j1 == ROWS - 1 || j2 == ROWS as i32 - 1 {
-			wake_up_chunk(mov_dt.chunks, mov_dt.index, (0, 1), (i1, 0));
+			wake_up_chunk(mov_dt, (0, 1), (i1, 0));
 		}
 
-		return true;
+		true
 	} else { // INFO: Element swap happening between two chunks
 		let wanted_chunk = get_wanted_chunk(mov_dt.index, i2, j2);
 
@@ -252,29 +292,22 @@ pub fn swap(grid: &mut Grid, i1: usize, j1: usize, i2: i32, j2: i32, mov_dt: &mu
 			
 				(grid[i1][j1], chunk.grid[x as usize][y as usize]) = (chunk.grid[x as usize][y as usize], grid[i1][j1]);
 
-				if !chunk.active {
-					chunk::activate(chunk);
-					chunk.dirty_rect.set_temp(x as usize, y as usize);
-				} else {
-					chunk.dirty_rect.set_temp(x as usize, y as usize);
-				}
+				mov_dt.wake_ups.push((wanted_chunk, (x as usize, y as usize)));
 				
-				return true;
+				true
 			},
-			_ => return false
+			_ => false
 		}
 	}
 }
 
+/*
+	INFO: Neighboring chunks are not woken up right away, the wake ups are collected and applied after the whole pass.
+	Chunks in the same pass can share a neighbor, so touching its state here would depend on thread scheduling
+*/
 #[inline]
-fn wake_up_chunk(chunks: &mut WorldChunks, index: (i32, i32), dir: (i32, i32), dirty_coord: (usize, usize)) {
-	if let Some(chunk) = chunks.get_mut(&(index.0 + dir.0, index.1 + dir.1)) {
-		if !chunk.active { 
-			chunk::activate(chunk);
-		} else {
-			chunk.dirty_rect.set_temp(dirty_coord.0, dirty_coord.1);
-		}
-	}
+fn wake_up_chunk(mov_dt: &mut MovData, dir: (i32, i32), dirty_coord: (usize, usize)) {
+	mov_dt.wake_ups.push(((mov_dt.index.0 + dir.0, mov_dt.index.1 + dir.1), dirty_coord));
 }
 
 // INFO: Gets the chunk that the element wants to move to
@@ -1,23 +1,21 @@
-use notan::{
-    draw::{Draw, DrawImages},
-    graphics::{Texture, TextureFilter},
-    math::Vec2,
-    prelude::Graphics,
-};
+use fastrand::Rng;
+use glam::Vec2;
 use rapier2d::math::Real;
 
 use crate::{
     phys_world::chunk_manager::WorldChunks, phys_world::el_movement::*, phys_world::element::*,
-    phys_world::element_actions::handle_actions,
+    phys_world::element_actions::{can_react, handle_actions, handle_reactions},
 };
 
-use super::{element_texture_handler::{ElementTexHandler, EL_TEX_WIDTH, EL_TEX_HEIGHT}, rapier_edge_gen::edges_from_chunk};
+use super::{electricity::handle_electricity, element_registry::{registry, Movement}, explosion::Explosion, heat::{self, handle_heat}, pressure::{self, push_cell, PressureField}, element_texture_handler::{ElementTexHandler, EL_TEX_WIDTH, EL_TEX_HEIGHT}, rapier_edge_gen::edges_from_chunk, wetness::handle_wetness};
 
 pub const COLS: usize = 256;
 pub const ROWS: usize = 144;
 pub const UPSCALE_FACTOR: f32 = 2.;
 
 pub type Grid = Box<[[Cell; ROWS]; COLS]>;
+// INFO: A neighboring chunk and the cell in it that has to be woken up
+pub type WakeUp = ((i32, i32), (usize, usize));
 
 pub struct Chunk {
     pub pos: (f32, f32),
@@ -28,22 +26,23 @@ pub struct Chunk {
     pub dirty_tex: bool,
     pub dirty_rect: DirtyRect,
     pub bytes: Vec<u8>,
-    texture: Texture,
     pub edges: Vec<Vec<rapier2d::math::Point<Real>>>,
     pub colliders_dirty: bool,
+    // INFO: Neighboring chunks (and the cell in them) this chunk moved elements into during its last update
+    pub wake_ups: Vec<WakeUp>,
+    // INFO: Last frame something needed this chunk to be loaded, chunks that were not needed for a while get unloaded
+    pub last_needed: u128,
+    pub pressure: PressureField,
+    // INFO: Detonations of the last update, the rigid bodies around them still have to be pushed
+    pub explosions: Vec<Explosion>,
+    // INFO: A liquid moved in this chunk since it was last leveled, see liquid_level.rs
+    pub liquid_moved: bool,
 }
 
 impl Chunk {
-    pub fn new(i: i32, j: i32, gfx: &mut Graphics) -> Self {
+    pub fn new(i: i32, j: i32) -> Self {
         let bytes = vec![0; COLS * ROWS * 4];
 
-        let texture = gfx
-            .create_texture()
-            .from_bytes(&bytes, COLS as u32, ROWS as u32)
-            .with_filter(TextureFilter::Nearest, TextureFilter::Nearest)
-            .build()
-            .unwrap();
-
         let grid = create_cells_array();
         let future_grid = grid.clone();
 
@@ -59,9 +58,13 @@ impl Chunk {
             dirty_tex: true,
             dirty_rect: DirtyRect::default(),
             bytes,
-            texture,
             edges: vec![],
             colliders_dirty: false,
+            wake_ups: vec![],
+            last_needed: 0,
+            pressure: PressureField::default(),
+            explosions: vec![],
+            liquid_moved: false,
         }
     }
 }
@@ -78,14 +81,32 @@ fn create_cells_array() -> Grid {
 
 pub struct MovData<'a> {
     pub chunks: &'a mut WorldChunks,
+    // INFO: The cells of the chunk as they were at the start of the frame
+    pub grid: &'a Grid,
     pub index: (i32, i32),
     pub keep_active: &'a mut bool,
     pub dirty_rect: &'a mut DirtyRect,
     pub bytes: &'a mut Vec<u8>,
-    pub colliders_dirty: &'a mut bool
+    pub colliders_dirty: &'a mut bool,
+    pub rng: &'a mut Rng,
+    pub wake_ups: &'a mut Vec<WakeUp>,
+    pub pressure: &'a mut PressureField,
+    pub explosions: &'a mut Vec<Explosion>,
+    // INFO: The wind of the weather, the same for the whole world
+    pub wind: Vec2
+}
+
+// INFO: Every chunk gets its own random stream each frame, so the result does not depend on which thread updated it or when
+pub fn chunk_seed(seed: u64, index: (i32, i32), frame_count: u128) -> u64 {
+    let mut hash = seed ^ 0x9E37_79B9_7F4A_7C15;
+    for value in [index.0 as u64, index.1 as u64, frame_count as u64] {
+        hash = (hash ^ value).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        hash ^= hash >> 31;
+    }
+    hash
 }
 
-pub fn update_chunk(chunk: &mut Chunk, chunks: &mut WorldChunks, frame_count: u128) {
+pub fn update_chunk(chunk: &mut Chunk, chunks: &mut WorldChunks, frame_count: u128, seed: u64, wind: Vec2) {
     if !chunk.active {
         return;
     }
@@ -93,10 +114,12 @@ pub fn update_chunk(chunk: &mut Chunk, chunks: &mut WorldChunks, frame_count: u1
     chunk.future_grid = chunk.grid.clone();
 
     let mut keep_active = false;
+    let (settled, reactive) = scan_dirty_rect(chunk);
+    let mut rng = Rng::with_seed(chunk_seed(seed, chunk.index, frame_count));
 
-    let flip_x = fastrand::bool();
+    let flip_x = rng.bool();
     for i_loop in chunk.dirty_rect.min_xy.0..=chunk.dirty_rect.max_xy.0 {
-        let flip_y = fastrand::bool();
+        let flip_y = rng.bool();
         for j_loop in chunk.dirty_rect.min_xy.1..=chunk.dirty_rect.max_xy.1 {
             let i = if flip_x {
                 chunk.dirty_rect.max_xy.0 - (i_loop - chunk.dirty_rect.min_xy.0)
@@ -114,46 +137,58 @@ pub fn update_chunk(chunk: &mut Chunk, chunks: &mut WorldChunks, frame_count: u1
 
             let mut mov_dt = MovData {
                 chunks,
+                grid: &chunk.grid,
                 index: chunk.index,
                 keep_active: &mut keep_active,
                 dirty_rect: &mut chunk.dirty_rect,
                 bytes: &mut chunk.bytes,
-                colliders_dirty: &mut chunk.colliders_dirty
+                colliders_dirty: &mut chunk.colliders_dirty,
+                rng: &mut rng,
+                wake_ups: &mut chunk.wake_ups,
+                pressure: &mut chunk.pressure,
+                explosions: &mut chunk.explosions,
+                wind
             };
 
             if chunk.grid[i][j].element == chunk.future_grid[i][j].element {
-                match chunk.grid[i][j].element {
-                    Element::Sand | Element::Dirt | Element::Gravel => {
-                        falling_sand(&mut chunk.future_grid, i, j, &mut mov_dt);
-                    }
-                    Element::SawDust | Element::Snow => {
-                        handle_actions(&mut chunk.future_grid, i, j, &mut mov_dt, frame_count);
+                // INFO: What an element does each frame comes from its definition, heat, reactions, wetness, sparks and actions run before the movement
+                if handle_heat(&mut chunk.future_grid, i, j, &mut mov_dt, settled) {
+                    continue;
+                }
+                if (reactive || on_edge(i, j)) && handle_reactions(&mut chunk.future_grid, i, j, &mut mov_dt) {
+                    continue;
+                }
+                if handle_wetness(&mut chunk.future_grid, i, j, &mut mov_dt) {
+                    continue;
+                }
+                handle_electricity(&mut chunk.future_grid, i, j, &mut mov_dt);
+                handle_actions(&mut chunk.future_grid, i, j, &mut mov_dt);
+                let movement = registry().get(chunk.grid[i][j].element).movement;
+                if movement != Movement::Static {
+                    push_cell(&mut chunk.future_grid, i, j, mov_dt.pressure);
+                }
+                match movement {
+                    Movement::Powder => {
                         falling_sand(&mut chunk.future_grid, i, j, &mut mov_dt);
                     }
-                    Element::Water | Element::Petrol | Element::Lava => {
-                        handle_actions(&mut chunk.future_grid, i, j, &mut mov_dt, frame_count);
-                        liquid_movement(&mut chunk.future_grid, i, j, &mut mov_dt);
-                    }
-                    Element::Steam | Element::Smoke => {
-                        gas_movement(&mut chunk.future_grid, i, j, &mut mov_dt);
+                    Movement::Liquid => {
+                        if liquid_movement(&mut chunk.future_grid, i, j, &mut mov_dt) {
+                            chunk.liquid_moved = true;
+                        }
                     }
-                    Element::Methane => {
-                        handle_actions(&mut chunk.future_grid, i, j, &mut mov_dt, frame_count);
+                    Movement::Gas => {
                         gas_movement(&mut chunk.future_grid, i, j, &mut mov_dt);
                     }
-                    Element::Fire => {
+                    Movement::Fire => {
                         fire_movement(&mut chunk.future_grid, i, j, &mut mov_dt);
                     }
-                    Element::FireworkShell => {
+                    Movement::FireworkShell => {
                         firework_shell_movement(&mut chunk.future_grid, i, j, &mut mov_dt);
                     }
-                    Element::FireworkEmber => {
+                    Movement::FireworkEmber => {
                         firework_ember_movement(&mut chunk.future_grid, i, j, &mut mov_dt);
                     }
-                    Element::Wood | Element::Coal | Element::Source | Element::Grass | Element::Ice => {
-                        handle_actions(&mut chunk.future_grid, i, j, &mut mov_dt, frame_count);
-                    }
-                    _ => (),
+                    Movement::Static => (),
                 }
             }
         }
@@ -170,12 +205,42 @@ pub fn update_chunk(chunk: &mut Chunk, chunks: &mut WorldChunks, frame_count: u1
     }
 }
 
+/*
+    INFO: Finds out from the dirty rect and the cells around it if heat can flow (some cell is not at the ambient temperature)
+    and if reactions can happen (two elements that react are there). If not, the cells inside the chunk skip looking at their neighbors for them
+*/
+fn scan_dirty_rect(chunk: &Chunk) -> (bool, bool) {
+    let (min, max) = (chunk.dirty_rect.min_xy, chunk.dirty_rect.max_xy);
+    // INFO: An empty dirty rect updates no cells
+    if min.0 > max.0 || min.1 > max.1 {
+        return (true, false);
+    }
+    let mut settled = true;
+    let mut present = vec![false; registry().elements().len()];
+    for column in &chunk.grid[min.0.saturating_sub(1)..=(max.0 + 1).min(COLS - 1)] {
+        for cell in &column[min.1.saturating_sub(1)..=(max.1 + 1).min(ROWS - 1)] {
+            settled &= heat::at_ambient(cell.temperature);
+            present[cell.element.0 as usize] = true;
+        }
+    }
+    (settled, can_react(&present))
+}
+
 pub fn activate(chunk: &mut Chunk) {
     chunk.active = true;
     chunk.dirty_tex = true;
     chunk.dirty_rect.reset();
 }
 
+pub fn wake_up(chunk: &mut Chunk, i: usize, j: usize) {
+    if !chunk.active {
+        activate(chunk);
+    }
+    chunk.dirty_rect.set_temp(i, j);
+}
+
+// INFO: The brush settings of the editor are passed through as they are
+#[allow(clippy::too_many_arguments)]
 pub fn modify_chunk_elements(
     chunk: &mut Chunk,
     i: i32,
@@ -191,10 +256,8 @@ pub fn modify_chunk_elements(
             for y in -brush_size / 2..brush_size / 2 {
                 if (((i as f32 + 0.5) - (i as f32 - x as f32)).powf(2.) + ((j as f32 + 0.5) - (j as f32 - y as f32)).powf(2.)) <= (brush_size as f32 / 2.).powf(2.) {
                     if empty_only && cell.element != Element::Air {
-                        if in_bound(i - x, j - y) {
-                            if chunk.grid[(i - x) as usize][(j - y) as usize].element == Element::Air {
-                                modify_chunk_element(chunk, i - x, j - y, cell, element_texs, edit_bodies);
-                            }
+                        if in_bound(i - x, j - y) && chunk.grid[(i - x) as usize][(j - y) as usize].element == Element::Air {
+                            modify_chunk_element(chunk, i - x, j - y, cell, element_texs, edit_bodies);
                         }
                     } else {
                         modify_chunk_element(chunk, i - x, j - y, cell, element_texs, edit_bodies);
@@ -248,24 +311,8 @@ pub fn modify_chunk_element(chunk: &mut Chunk, i: i32, j: i32, cell: &Cell, elem
 }
 
 pub fn explode_chunk(chunk: &mut Chunk, i: i32, j: i32, radius: i32, force: f32) {
-    for x in -radius / 2..=radius / 2 {
-        for y in -radius / 2..radius / 2 {
-            if ((i - (i - x)).pow(2) + (j - (j - y)).pow(2)) <= (radius / 2).pow(2) {
-                if in_bound(i - x, j - y) {
-                    if chunk.grid[(i - x) as usize][(j - y) as usize].state != State::Solid
-                        && chunk.grid[(i - x) as usize][(j - y) as usize].element != Element::Air
-                    {
-                        let mut angle = Vec2::new(x as f32, y as f32);
-                        angle = angle.normalize_or_zero() * force * -1.;
-                        chunk.grid[(i - x) as usize][(j - y) as usize].velocity += angle;
-                        if angle.x.abs() > 0.5 && angle.y.abs() > 0.5 {
-                            activate(chunk)
-                        }
-                    }
-                }
-            }
-        }
-    }
+    pressure::explode(chunk, i, j, radius, force);
+    activate(chunk);
 }
 
 pub fn get_chunk_cell(chunk: &Chunk, i: i32, j: i32) -> Option<&Cell> {
@@ -283,44 +330,35 @@ pub fn mouse_in_chunk(chunk_index: (f32, f32), mouse_world: (f32, f32)) -> (i32,
     mouse_pos
 }
 
-pub fn in_bound(i: i32, j: i32) -> bool {
-    i >= 0 && j >= 0 && i < COLS as i32 && j < ROWS as i32
+pub fn is_empty(chunk: &Chunk) -> bool {
+    chunk.grid.iter().all(|column| column.iter().all(|cell| cell.element == Element::Air))
 }
 
-pub fn render_chunk(chunk: &mut Chunk, gfx: &mut Graphics, draw: &mut Draw, update_chunks: bool) {
-    update_chunk_tex_data(chunk, gfx, update_chunks);
-
-    draw.image(&chunk.texture)
-        .size(COLS as f32 * UPSCALE_FACTOR, ROWS as f32 * UPSCALE_FACTOR)
-        .position(chunk.pos.0, chunk.pos.1);
+// INFO: The index of the chunk a world position (the same space as the mouse) is in
+pub fn world_to_chunk_index(world: (f32, f32)) -> (i32, i32) {
+    (
+        (world.0 / (COLS as f32 * UPSCALE_FACTOR)).floor() as i32,
+        (world.1 / (ROWS as f32 * UPSCALE_FACTOR)).floor() as i32,
+    )
 }
 
-fn update_chunk_tex_data(chunk: &mut Chunk, gfx: &mut Graphics, update_chunks: bool) {
-    if chunk.dirty_tex {
-        /*
-            INFO: Texture data is updated at the same time as movements are done
-            we need to only manualy update the texture data when the chunks are paused
-        */
-        if !update_chunks {
-            update_bytes(chunk);
-        }
-
-        gfx.update_texture(&mut chunk.texture)
-            .with_data(&chunk.bytes)
-            .update()
-            .unwrap();
+pub fn in_bound(i: i32, j: i32) -> bool {
+    i >= 0 && j >= 0 && i < COLS as i32 && j < ROWS as i32
+}
 
-        chunk.dirty_tex = false;
-    }
+// INFO: Cells on the edge have neighbors in the chunks around, what the chunk knows about its own cells says nothing about those
+pub fn on_edge(i: usize, j: usize) -> bool {
+    i == 0 || j == 0 || i == COLS - 1 || j == ROWS - 1
 }
 
-fn update_bytes(chunk: &mut Chunk) {
+// INFO: Rebuilds the whole byte buffer from the grid colors, the renderer uploads these bytes as the chunk texture
+pub fn update_bytes(chunk: &mut Chunk) {
     for i in 0..chunk.bytes.len() / 4 {
         chunk.bytes[i * 4..i * 4 + 4].copy_from_slice(&chunk.grid[i % COLS][i / COLS].color);
     }
 }
 
-pub fn update_byte(bytes: &mut Vec<u8>, i: usize, j: usize, color: &[u8; 4]) {
+pub fn update_byte(bytes: &mut [u8], i: usize, j: usize, color: &[u8; 4]) {
     let index = j * COLS + i;
     bytes[index * 4..index * 4 + 4].copy_from_slice(color);
 }
@@ -366,8 +404,9 @@ impl DirtyRect {
         self.temp_max_xy = (0, 0);
     }
 
+    // INFO: A chunk where nothing changed has its min past its max, that rect is empty
     pub fn get_area(&self) -> u32 {
-        (self.max_xy.0 - self.min_xy.0) as u32 * (self.max_xy.1 - self.min_xy.1) as u32
+        self.max_xy.0.saturating_sub(self.min_xy.0) as u32 * self.max_xy.1.saturating_sub(self.min_xy.1) as u32
     }
 }
 
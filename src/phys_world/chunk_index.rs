@@ -0,0 +1,70 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+/*
+    INFO: Spatial index of the loaded chunks, rows of chunk columns kept in order.
+    Iterating it always gives the same order (top to bottom, left to right), which the update passes rely on,
+    and rectangles of chunks (the camera view, a brush) can be looked up without touching every loaded chunk
+*/
+#[derive(Default)]
+pub struct ChunkIndex {
+    rows: BTreeMap<i32, BTreeSet<i32>>,
+    len: usize,
+}
+
+impl ChunkIndex {
+    pub fn insert(&mut self, index: (i32, i32)) {
+        if self.rows.entry(index.1).or_default().insert(index.0) {
+            self.len += 1;
+        }
+    }
+
+    pub fn remove(&mut self, index: (i32, i32)) {
+        if let Some(row) = self.rows.get_mut(&index.1) {
+            if row.remove(&index.0) {
+                self.len -= 1;
+            }
+            if row.is_empty() {
+                self.rows.remove(&index.1);
+            }
+        }
+    }
+
+    pub fn contains(&self, index: (i32, i32)) -> bool {
+        self.rows.get(&index.1).is_some_and(|row| row.contains(&index.0))
+    }
+
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.len = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.rows.iter().flat_map(|(j, row)| row.iter().map(move |i| (*i, *j)))
+    }
+
+    // INFO: All loaded chunks inside of the rectangle, min and max are inclusive
+    pub fn in_rect(&self, min: (i32, i32), max: (i32, i32)) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let rows = if min.1 <= max.1 { self.rows.range(min.1..=max.1) } else { self.rows.range(0..0) };
+        rows.flat_map(move |(j, row)| {
+            let columns = if min.0 <= max.0 { row.range(min.0..=max.0) } else { row.range(0..0) };
+            columns.map(move |i| (*i, *j))
+        })
+    }
+
+    // INFO: The smallest and largest chunk index in both directions
+    pub fn bounds(&self) -> Option<((i32, i32), (i32, i32))> {
+        let min_y = *self.rows.keys().next()?;
+        let max_y = *self.rows.keys().next_back()?;
+        let min_x = self.rows.values().filter_map(|row| row.first()).min()?;
+        let max_x = self.rows.values().filter_map(|row| row.last()).max()?;
+        Some(((*min_x, min_y), (*max_x, max_y)))
+    }
+}
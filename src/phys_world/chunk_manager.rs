@@ -1,140 +1,339 @@
 use ahash::RandomState;
+use glam::Vec2;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
+    env, fs,
+    hash::{BuildHasher, Hash, Hasher},
+    io,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
     thread,
     time::{Duration, Instant},
 };
 
-use notan::{
-    draw::*,
-    prelude::*,
-    random::rand::{prelude::SliceRandom, thread_rng},
-};
-
 use crate::{
-    camera::Camera2D,
-    input_manager::get_mouse_in_world,
-    phys_world::chunk::{self, Chunk, COLS, ROWS, UPSCALE_FACTOR},
+    phys_world::chunk::{self, Chunk, COLS, ROWS},
     phys_world::element::{sand_element, Cell},
-    DebugInfo,
 };
 
-use super::{element_texture_handler::ElementTexHandler, rapier_world_handler::PHYS_SCALE, element::solid_element};
+use super::{element_texture_handler::ElementTexHandler, element::solid_element, chunk_index::ChunkIndex, explosion::Explosion, liquid_level, pressure, rapier_edge_gen::edges_from_chunk, world_save};
 
 pub type WorldChunks = HashMap<(i32, i32), Chunk, RandomState>;
 
+// INFO: How many frames a chunk has to be unneeded before it gets unloaded, so chunks at the edge of the view do not flicker in and out
+const UNLOAD_DELAY: u128 = 120;
+
+// INFO: Every chunk manager gets its own directory for unloaded chunks, even when there are multiple in one process
+static CHUNK_DIR_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// INFO: Editing input for a single frame, filled in by whatever is driving the simulation (window, CLI, tests)
+#[derive(Default)]
+pub struct EditInput {
+    pub mouse_world: (f32, f32),
+    pub place: bool,
+    pub explode_force: Option<f32>,
+}
+
 pub struct ChunkManager {
     pub chunks: WorldChunks,
     pub selected_element: Cell,
     pub modify: bool,
     pub brush_size: i32,
     pub update_chunks: bool,
-    pub range_x: (i32, i32),
-    pub range_y: (i32, i32),
+    // INFO: Which chunks are loaded, it is kept in sync with `chunks` by insert_chunk/remove_chunk
+    pub chunk_index: ChunkIndex,
+    // INFO: Chunks that were unloaded into `chunk_dir`, they are read back once something needs them again
+    pub stored_chunks: HashSet<(i32, i32), RandomState>,
+    pub chunk_dir: PathBuf,
+    /*
+        INFO: The smallest and largest index of every chunk that was created so far, the world only grows through the view.
+        Moving content only loads chunks inside of it, past its edges is the bottom and the sides of the world
+    */
+    pub world_bounds: Option<((i32, i32), (i32, i32))>,
+    // INFO: World rectangle (the same space as the mouse) that always stays loaded, usually what the camera sees
+    pub view: Option<((f32, f32), (f32, f32))>,
     pub hovering_cell: (Cell, (i32, i32), (i32, i32)),
     pub update_time: f32,
     pub replace_air: bool,
     pub chunks_update_time: Duration,
     pub chunks_render_time: Duration,
     pub num_of_threads: [usize; 4],
-    font: Font,
     pub chunk_frame_count: u128,
     pub tex_handler: ElementTexHandler,
     pub edit_body_elements: bool,
+    // INFO: The same seed with the same edits always produces the same world, no matter the amount of threads
+    pub seed: u64,
+    // INFO: 0 means as many threads as there are chunk pools
+    pub max_threads: usize,
+    // INFO: Pushes everything in the air, set by the weather
+    pub wind: Vec2,
 }
 
-impl ChunkManager {
-    pub fn new(gfx: &mut Graphics) -> Self {
-        let range_x = (-2, 2);
-        let range_y = (-2, 2);
-
-        let mut chunks: WorldChunks = HashMap::default();
-        for i in range_x.0..=range_x.1 {
-            for j in range_y.0..=range_y.1 {
-                chunks.insert((i, j), Chunk::new(i, j, gfx));
-            }
-        }
+impl Default for ChunkManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        Self {
-            chunks,
+impl ChunkManager {
+    pub fn new() -> Self {
+        let chunk_dir = env::temp_dir().join(format!(
+            "falling_sand_chunks_{}_{}",
+            std::process::id(),
+            CHUNK_DIR_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let mut chunk_manager = Self {
+            chunks: HashMap::default(),
             selected_element: solid_element(),
             modify: true,
             brush_size: 32,
             update_chunks: true,
-            range_x,
-            range_y,
+            chunk_index: ChunkIndex::default(),
+            stored_chunks: HashSet::default(),
+            chunk_dir,
+            world_bounds: None,
+            view: None,
             hovering_cell: (sand_element(), (0, 0), (0, 0)),
             update_time: 0.,
             replace_air: true,
             chunks_update_time: Duration::default(),
             chunks_render_time: Duration::default(),
             num_of_threads: [0; 4],
-            font: gfx
-                .create_font(include_bytes!("../assets/UbuntuMono.ttf"))
-                .unwrap(),
             chunk_frame_count: 0,
             tex_handler: ElementTexHandler::new(),
-            edit_body_elements: true
+            edit_body_elements: true,
+            seed: fastrand::u64(..),
+            max_threads: 0,
+            wind: Vec2::ZERO,
+        };
+
+        // INFO: The world starts out with the chunks around the origin, the rest gets created once it is needed
+        for i in -2..=2 {
+            for j in -2..=2 {
+                chunk_manager.insert_chunk(Chunk::new(i, j));
+            }
         }
+
+        chunk_manager
     }
-    
-    pub fn update_brush(&mut self, app: &mut App) {
-        if app.mouse.is_scrolling() {
-            self.brush_size += app.mouse.wheel_delta.y as i32 / 6;
 
-            if self.brush_size <= 0 {
-                self.brush_size = 1;
+    pub fn insert_chunk(&mut self, chunk: Chunk) {
+        let (x, y) = chunk.index;
+        self.world_bounds = Some(match self.world_bounds {
+            Some((min, max)) => ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y))),
+            None => ((x, y), (x, y)),
+        });
+        self.chunk_index.insert(chunk.index);
+        self.chunks.insert(chunk.index, chunk);
+    }
+
+    pub fn remove_chunk(&mut self, index: (i32, i32)) -> Option<Chunk> {
+        self.chunk_index.remove(index);
+        self.chunks.remove(&index)
+    }
+
+    // INFO: Removes every chunk, including the ones that were unloaded to disk
+    pub fn clear_chunks(&mut self) {
+        self.chunks.clear();
+        self.chunk_index.clear();
+        self.world_bounds = None;
+        for index in std::mem::take(&mut self.stored_chunks) {
+            let _ = fs::remove_file(self.stored_chunk_path(index));
+        }
+    }
+
+    /*
+        INFO: Loads every chunk that is needed and unloads the ones that were not needed for a while.
+        A chunk is needed when it is in the view, active, in `keep` or next to an active or kept chunk inside the world bounds.
+        Returns the chunks that were unloaded, so their colliders can be removed
+    */
+    pub fn stream_chunks(&mut self, keep: &[(i32, i32)]) -> Vec<(i32, i32)> {
+        let mut needed = BTreeSet::new();
+        if let Some((min, max)) = self.view {
+            let (min, max) = (chunk::world_to_chunk_index(min), chunk::world_to_chunk_index(max));
+            for j in min.1..=max.1 {
+                for i in min.0..=max.0 {
+                    needed.insert((i, j));
+                }
+            }
+        }
+
+        // INFO: Active chunks and kept chunks also need their neighbors, so elements have somewhere to move into
+        let mut centers: Vec<(i32, i32)> = self.chunk_index.iter().filter(|index| self.chunks[index].active).collect();
+        centers.extend_from_slice(keep);
+        for center in centers {
+            for x in -1..=1 {
+                for y in -1..=1 {
+                    let neighbor = (center.0 + x, center.1 + y);
+                    if self.in_world(neighbor) {
+                        needed.insert(neighbor);
+                    }
+                }
+            }
+        }
+
+        for index in needed {
+            if let Some(chunk) = self.chunks.get_mut(&index) {
+                chunk.last_needed = self.chunk_frame_count;
+                continue;
+            }
+
+            let mut chunk = self.load_chunk(index);
+            chunk.last_needed = self.chunk_frame_count;
+            self.insert_chunk(chunk);
+        }
+
+        let mut unloaded = vec![];
+        let loaded: Vec<(i32, i32)> = self.chunk_index.iter().collect();
+        for index in loaded {
+            let chunk = &self.chunks[&index];
+            if chunk.active || self.chunk_frame_count.saturating_sub(chunk.last_needed) < UNLOAD_DELAY {
+                continue;
+            }
+
+            let chunk = self.remove_chunk(index).unwrap();
+            // INFO: Empty chunks are not written, a chunk that was never stored is created empty anyway
+            if !chunk::is_empty(&chunk) {
+                if let Err(e) = self.store_chunk(&chunk) {
+                    eprintln!("Could not unload chunk {:?}: {}", index, e);
+                    self.insert_chunk(chunk);
+                    continue;
+                }
+            }
+            unloaded.push(index);
+        }
+
+        unloaded
+    }
+
+    fn load_neighbors(&mut self, index: (i32, i32)) {
+        for x in -1..=1 {
+            for y in -1..=1 {
+                let neighbor = (index.0 + x, index.1 + y);
+                if !self.chunks.contains_key(&neighbor) && self.in_world(neighbor) {
+                    let mut chunk = self.load_chunk(neighbor);
+                    chunk.last_needed = self.chunk_frame_count;
+                    self.insert_chunk(chunk);
+                }
             }
         }
     }
 
-    pub fn update_chunk_edit(&mut self, app: &mut App, camera: &Camera2D) {
-        let mouse_world = get_mouse_in_world(
-            &(app.mouse.x, app.mouse.y),
-            (app.window().width() as i32, app.window().height() as i32),
-            camera,
-        );
+    // INFO: Every chunk that was unloaded to disk is inside the bounds too, it was created before
+    fn in_world(&self, index: (i32, i32)) -> bool {
+        self.world_bounds
+            .is_some_and(|(min, max)| index.0 >= min.0 && index.0 <= max.0 && index.1 >= min.1 && index.1 <= max.1)
+    }
 
-        for i in self.range_x.0..=self.range_x.1 {
-            for j in (self.range_y.0..=self.range_y.1).rev() {
-                let key = &(i, j);
+    // INFO: Reads the chunk back from disk if it was unloaded before, otherwise it is a new empty chunk
+    fn load_chunk(&mut self, index: (i32, i32)) -> Chunk {
+        if self.stored_chunks.remove(&index) {
+            let path = self.stored_chunk_path(index);
+            let chunk = world_save::read_chunk_file(&path);
+            let _ = fs::remove_file(&path);
+
+            match chunk {
+                Ok(mut chunk) => {
+                    chunk::update_bytes(&mut chunk);
+                    chunk.colliders_dirty = true;
+                    edges_from_chunk(&mut chunk);
+                    return chunk;
+                }
+                Err(e) => eprintln!("Could not load chunk {:?}, it is replaced with an empty one: {}", index, e),
+            }
+        }
 
-                if let Some(chunk) = self.chunks.get_mut(key) {
-                    let mouse = chunk::mouse_in_chunk(chunk.pos, mouse_world);
+        let mut chunk = Chunk::new(index.0, index.1);
+        chunk.active = false;
+        chunk
+    }
 
-                    if app.mouse.left_is_down() && self.modify {
-                        chunk::modify_chunk_elements(
-                            chunk,
-                            mouse.0,
-                            mouse.1,
-                            self.brush_size,
-                            &self.selected_element,
-                            self.replace_air,
-                            self.edit_body_elements,
-                            &self.tex_handler,
-                        );
-                    }
+    fn store_chunk(&mut self, chunk: &Chunk) -> io::Result<()> {
+        fs::create_dir_all(&self.chunk_dir)?;
+        world_save::write_chunk_file(chunk, &self.stored_chunk_path(chunk.index))?;
+        self.stored_chunks.insert(chunk.index);
+        Ok(())
+    }
+
+    pub fn read_stored_chunk(&self, index: (i32, i32)) -> io::Result<Chunk> {
+        world_save::read_chunk_file(&self.stored_chunk_path(index))
+    }
 
-                    if app.mouse.right_is_down() && self.modify {
+    fn stored_chunk_path(&self, index: (i32, i32)) -> PathBuf {
+        self.chunk_dir.join(format!("chunk_{}_{}.bin", index.0, index.1))
+    }
+    
+    pub fn update_chunk_edit(&mut self, edit: &EditInput) {
+        let mouse_world = edit.mouse_world;
+
+        // INFO: Only the chunks the brush (or the explosion, which is twice as big) can reach
+        let mouse_chunk = chunk::world_to_chunk_index(mouse_world);
+        let reach = (self.brush_size / ROWS as i32) + 1;
+        let indices: Vec<(i32, i32)> = self
+            .chunk_index
+            .in_rect((mouse_chunk.0 - reach, mouse_chunk.1 - reach), (mouse_chunk.0 + reach, mouse_chunk.1 + reach))
+            .collect();
+
+        for key in &indices {
+            if let Some(chunk) = self.chunks.get_mut(key) {
+                let mouse = chunk::mouse_in_chunk(chunk.pos, mouse_world);
+
+                if edit.place && self.modify {
+                    chunk::modify_chunk_elements(
+                        chunk,
+                        mouse.0,
+                        mouse.1,
+                        self.brush_size,
+                        &self.selected_element,
+                        self.replace_air,
+                        self.edit_body_elements,
+                        &self.tex_handler,
+                    );
+                }
+
+                if let Some(force) = edit.explode_force {
+                    if self.modify {
                         chunk::explode_chunk(
                             chunk,
                             mouse.0,
                             mouse.1,
                             self.brush_size * 2,
-                            4. * app.timer.delta_f32() * 90.,
+                            force,
                         );
                     }
+                }
 
-                    if let Some(c) = chunk::get_chunk_cell(chunk, mouse.0, mouse.1) {
-                        self.hovering_cell.0 = c.to_owned();
-                        self.hovering_cell.1 = chunk.index;
-                        self.hovering_cell.2 = mouse;
-                    }
+                if let Some(c) = chunk::get_chunk_cell(chunk, mouse.0, mouse.1) {
+                    self.hovering_cell.0 = c.to_owned();
+                    self.hovering_cell.1 = chunk.index;
+                    self.hovering_cell.2 = mouse;
                 }
             }
         }
     }
 
+    // INFO: Places an element into a rectangle of world cells, the rectangle can span over multiple chunks
+    pub fn fill_cells(&mut self, min: (i32, i32), max: (i32, i32), cell: &Cell) {
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                let index = (x.div_euclid(COLS as i32), y.div_euclid(ROWS as i32));
+                if let Some(chunk) = self.chunks.get_mut(&index) {
+                    chunk::modify_chunk_element(chunk, x.rem_euclid(COLS as i32), y.rem_euclid(ROWS as i32), cell, &self.tex_handler, true);
+                }
+            }
+        }
+    }
+
+    // INFO: The first and last cell of the loaded chunks in world cell coordinates
+    pub fn world_cell_bounds(&self) -> ((i32, i32), (i32, i32)) {
+        let (min, max) = self.chunk_index.bounds().unwrap_or(((0, 0), (-1, -1)));
+        (
+            (min.0 * COLS as i32, min.1 * ROWS as i32),
+            ((max.0 + 1) * COLS as i32 - 1, (max.1 + 1) * ROWS as i32 - 1),
+        )
+    }
+
     pub fn update_chunks_fixed(&mut self) {
         if self.update_chunks {
             let now = Instant::now();
@@ -143,42 +342,26 @@ impl ChunkManager {
 
             let mut all_chunks_to_update = vec![];
 
+            // INFO: Elements of active chunks can always move into their neighbors inside the world, even at the edge of what is loaded
+            let active: Vec<(i32, i32)> = self.chunk_index.iter().filter(|index| self.chunks[index].active).collect();
+            for index in active {
+                self.load_neighbors(index);
+            }
+
             // INFO: Separate chunks into updatable chunk pools
             let mut chunks_to_update = (vec![], vec![], vec![], vec![]);
-            for j in self.range_y.0..=self.range_y.1 {
-                if j % 2 == 0 {
-                    for i in self.range_x.0..=self.range_x.1 {
-                        if i % 2 == 0 {
-                            if let Some(chunk) = self.chunks.get(&(i, j)) {
-                                if chunk.active {
-                                    chunks_to_update.0.push((i, j));
-                                }
-                            }
-                        } else {
-                            if let Some(chunk) = self.chunks.get(&(i, j)) {
-                                if chunk.active {
-                                    chunks_to_update.1.push((i, j));
-                                }
-                            }
-                        }
-                    }
-                } else {
-                    for i in self.range_x.0..=self.range_x.1 {
-                        if i % 2 == 0 {
-                            if let Some(chunk) = self.chunks.get(&(i, j)) {
-                                if chunk.active {
-                                    chunks_to_update.2.push((i, j));
-                                }
-                            }
-                        } else {
-                            if let Some(chunk) = self.chunks.get(&(i, j)) {
-                                if chunk.active {
-                                    chunks_to_update.3.push((i, j));
-                                }
-                            }
-                        }
-                    }
+            for (i, j) in self.chunk_index.iter() {
+                if !self.chunks[&(i, j)].active {
+                    continue;
                 }
+
+                let chunk_pool = match (j % 2 == 0, i % 2 == 0) {
+                    (true, true) => &mut chunks_to_update.0,
+                    (true, false) => &mut chunks_to_update.1,
+                    (false, true) => &mut chunks_to_update.2,
+                    (false, false) => &mut chunks_to_update.3,
+                };
+                chunk_pool.push((i, j));
             }
 
             if !chunks_to_update.0.is_empty() {
@@ -197,15 +380,24 @@ impl ChunkManager {
             if !all_chunks_to_update.is_empty() {
                 self.num_of_threads = [0; 4];
                 let mut order: Vec<usize> = (0..all_chunks_to_update.len()).collect();
-                order.shuffle(&mut thread_rng());
+                fastrand::Rng::with_seed(self.seed.wrapping_add(self.chunk_frame_count as u64)).shuffle(&mut order);
 
                 for i in order {
                     self.update_select_chunks(&all_chunks_to_update[i], i);
+                    self.apply_wake_ups(&all_chunks_to_update[i]);
                 }
             } else if all_chunks_to_update.is_empty() {
                 self.num_of_threads = [0; 4];
             }
 
+            for index in pressure::update_pressure(&mut self.chunks, &self.chunk_index) {
+                self.load_neighbors(index);
+            }
+
+            if self.chunk_frame_count.is_multiple_of(liquid_level::LEVEL_INTERVAL) {
+                liquid_level::level_liquids(&mut self.chunks, &self.chunk_index, self.seed, self.chunk_frame_count);
+            }
+
             self.chunks_update_time = now.elapsed();
         }
     }
@@ -230,14 +422,14 @@ impl ChunkManager {
                             .dirty_rect
                             .get_area()
                     {
-                        ordered_chunks_to_update.insert(i, chunk_index.clone());
+                        ordered_chunks_to_update.insert(i, *chunk_index);
                         inserted = true;
                         break;
                     }
                 }
                 if !inserted {
                     ordered_chunks_to_update
-                        .insert(ordered_chunks_to_update.len(), chunk_index.clone());
+                        .insert(ordered_chunks_to_update.len(), *chunk_index);
                 }
             }
 
@@ -271,25 +463,41 @@ impl ChunkManager {
                 chunk_pools_to_update.push(chunk_pool);
             }
 
+            // INFO: Merge pools together until they fit into the allowed amount of threads
+            if self.max_threads > 0 {
+                while chunk_pools_to_update.len() > self.max_threads {
+                    let chunk_pool = chunk_pools_to_update.pop().unwrap();
+                    let last = chunk_pools_to_update.len() - 1;
+                    chunk_pools_to_update[last].extend(chunk_pool);
+                }
+            }
+
             // Update the chunks
             if chunk_pools_to_update.len() > 1 {
-                // INFO: Only create threads if there is more than one pool to update
+                // INFO: Only create threads if there is more than one pool to update.
+                // All pools are taken out before the first thread starts, the threads read the chunks map while they run
+                let mut chunk_pools = vec![];
                 for chunk_pool_indices in chunk_pools_to_update {
                     let mut chunk_pool = vec![];
                     for chunk_index in &chunk_pool_indices {
                         chunk_pool.push(self.chunks.remove(chunk_index).unwrap());
                     }
+                    chunk_pools.push(chunk_pool);
+                }
 
+                for mut chunk_pool in chunk_pools {
                     let ptr = RawPtrHolder {
                         ptr: &mut self.chunks as *mut WorldChunks,
                     };
 
                     let frame_count = self.chunk_frame_count;
+                    let seed = self.seed;
+                    let wind = self.wind;
                     let handle = thread::spawn(move || {
                         let world_chunks_ptr = ptr;
                         unsafe {
-                            for i in 0..chunk_pool.len() {
-                                chunk::update_chunk(&mut chunk_pool[i], &mut *world_chunks_ptr.ptr, frame_count);
+                            for chunk in chunk_pool.iter_mut() {
+                                chunk::update_chunk(chunk, &mut *world_chunks_ptr.ptr, frame_count, seed, wind);
                             }
                         }
                         chunk_pool
@@ -309,109 +517,98 @@ impl ChunkManager {
                 }
             } else {
                 // INFO: Update single pool
-                for i in 0..chunk_pools_to_update[0].len() {
-                    let mut chunk = self.chunks.remove(&chunk_pools_to_update[0][i]).unwrap();
-                    chunk::update_chunk(&mut chunk, &mut self.chunks, self.chunk_frame_count);
+                for index in &chunk_pools_to_update[0] {
+                    let mut chunk = self.chunks.remove(index).unwrap();
+                    chunk::update_chunk(&mut chunk, &mut self.chunks, self.chunk_frame_count, self.seed, self.wind);
                     self.chunks.insert(chunk.index, chunk);
                 }
             }
         } else {
             // INFO: Update single chunk
             let mut chunk = self.chunks.remove(&chunks_to_update[0]).unwrap();
-            chunk::update_chunk(&mut chunk, &mut self.chunks, self.chunk_frame_count);
+            chunk::update_chunk(&mut chunk, &mut self.chunks, self.chunk_frame_count, self.seed, self.wind);
             self.chunks.insert(chunks_to_update[0], chunk);
         }
     }
 
-    pub fn render(&mut self, gfx: &mut Graphics, draw: &mut Draw) {
-        let now = Instant::now();
-        for chunk in self.chunks.values_mut() {
-            chunk::render_chunk(chunk, gfx, draw, self.update_chunks);
+    // INFO: Wakes up the neighbors that the updated chunks moved elements into, always in the same order
+    fn apply_wake_ups(&mut self, updated_chunks: &[(i32, i32)]) {
+        for index in updated_chunks {
+            let wake_ups = match self.chunks.get_mut(index) {
+                Some(chunk) => std::mem::take(&mut chunk.wake_ups),
+                None => continue,
+            };
+
+            for (neighbor, (x, y)) in wake_ups {
+                if let Some(chunk) = self.chunks.get_mut(&neighbor) {
+                    chunk::wake_up(chunk, x, y);
+                    self.load_neighbors(neighbor);
+                }
+            }
         }
-        self.chunks_render_time = now.elapsed();
     }
 
-    pub fn debug_render(&mut self, draw: &mut Draw, debug_info: &DebugInfo) {
-        if debug_info.debug_chunk_coords {
-            for index in self.chunks.keys() {
-                draw.text(&self.font, &format!("{}, {}", index.0, index.1))
-                    .position(
-                        (COLS as f32 / 2. + (index.0 as f32 * COLS as f32)) * UPSCALE_FACTOR,
-                        (ROWS as f32 / 2. + (index.1 as f32 * ROWS as f32)) * UPSCALE_FACTOR,
-                    )
-                    .h_align_center()
-                    .v_align_middle();
+    // INFO: The detonations of the last update in chunk order, taking them clears them from the chunks
+    pub fn take_explosions(&mut self) -> Vec<Explosion> {
+        let mut explosions = vec![];
+        for index in self.chunk_index.iter() {
+            if let Some(chunk) = self.chunks.get_mut(&index) {
+                explosions.append(&mut chunk.explosions);
             }
         }
+        explosions
+    }
 
-        if debug_info.debug_chunk_bounds {
-            for index in self.chunks.keys() {
-                draw.rect(
-                    (
-                        index.0 as f32 * COLS as f32 * UPSCALE_FACTOR + 0.5,
-                        index.1 as f32 * ROWS as f32 * UPSCALE_FACTOR + 0.5,
-                    ),
-                    (
-                        COLS as f32 * UPSCALE_FACTOR - 1.,
-                        ROWS as f32 * UPSCALE_FACTOR - 1.,
-                    ),
-                )
-                .fill_color(Color::from_rgba(0., 0., 0., 0.))
-                .stroke_color(if self.chunks.get(index).unwrap().active {
-                    Color::GREEN
-                } else {
-                    Color::RED
-                })
-                .stroke(1.);
+    /*
+        INFO: Hash of every cell in the world, used to check that two runs ended up with the same world.
+        Unloaded chunks are read back for it and empty chunks are skipped, so it does not matter which chunks happen to be loaded
+    */
+    pub fn world_checksum(&self) -> u64 {
+        let mut indices: Vec<(i32, i32)> = self.chunk_index.iter().chain(self.stored_chunks.iter().copied()).collect();
+        indices.sort();
+
+        let mut hasher = RandomState::with_seeds(1, 2, 3, 4).build_hasher();
+        for index in indices {
+            let stored;
+            let chunk = match self.chunks.get(&index) {
+                Some(chunk) => chunk,
+                None => match self.read_stored_chunk(index) {
+                    Ok(chunk) => {
+                        stored = chunk;
+                        &stored
+                    }
+                    Err(_) => continue,
+                },
+            };
+            if chunk::is_empty(chunk) {
+                continue;
             }
-        }
 
-        if debug_info.debug_dirty_rects {
-            for (index, chunk) in self.chunks.iter() {
-                if chunk.active {
-                    draw.rect(
-                        (
-                            (index.0 as f32 * COLS as f32 + chunk.dirty_rect.min_xy.0 as f32)
-                                * UPSCALE_FACTOR,
-                            (index.1 as f32 * ROWS as f32 + chunk.dirty_rect.min_xy.1 as f32)
-                                * UPSCALE_FACTOR,
-                        ),
-                        (
-                            (chunk.dirty_rect.max_xy.0 - chunk.dirty_rect.min_xy.0) as f32
-                                * UPSCALE_FACTOR,
-                            (chunk.dirty_rect.max_xy.1 - chunk.dirty_rect.min_xy.1) as f32
-                                * UPSCALE_FACTOR,
-                        ),
-                    )
-                    .fill_color(Color::from_rgba(0., 0., 0., 0.))
-                    .stroke_color(Color::BLUE)
-                    .stroke(1.);
+            index.hash(&mut hasher);
+            for column in chunk.grid.iter() {
+                for cell in column.iter() {
+                    cell.element.hash(&mut hasher);
+                    cell.color.hash(&mut hasher);
+                    cell.lifetime.hash(&mut hasher);
+                    cell.temperature.to_bits().hash(&mut hasher);
+                    cell.charge.hash(&mut hasher);
+                    cell.wetness.hash(&mut hasher);
+                    cell.free_falling.hash(&mut hasher);
+                    cell.velocity.x.to_bits().hash(&mut hasher);
+                    cell.velocity.y.to_bits().hash(&mut hasher);
                 }
             }
-        }
-
-        if debug_info.debug_chunk_edges {
-            for (index, chunk) in self.chunks.iter() {
-                if !chunk.edges.is_empty() {
-                    let mut i = 0;
-                    for line in chunk.edges.iter() {
-                        if !line.is_empty() {
-                            let mut prev_point = &line[0];
-                            for point in line {
-                                draw.line(
-                                    ((index.0 as f32 * COLS as f32 / (PHYS_SCALE / UPSCALE_FACTOR) + prev_point.x) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR,
-                                     (index.1 as f32 * ROWS as f32 / (PHYS_SCALE / UPSCALE_FACTOR) + prev_point.y) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR),
-                                    ((index.0 as f32 * COLS as f32 / (PHYS_SCALE / UPSCALE_FACTOR) + point.x) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR,
-                                     (index.1 as f32 * ROWS as f32 / (PHYS_SCALE / UPSCALE_FACTOR) + point.y) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR)
-                                ).color(Color::from_bytes(i * 100 + 150, i * 25, i * 50 + 150, 255));
-                                prev_point = point;
-                            }
-                            i += 1;
-                        }
-                    }
-                }
+            for pressure in chunk.pressure.pressure.iter() {
+                pressure.to_bits().hash(&mut hasher);
             }
         }
+        hasher.finish()
+    }
+}
+
+impl Drop for ChunkManager {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.chunk_dir);
     }
 }
 
@@ -421,3 +618,33 @@ struct RawPtrHolder {
 
 unsafe impl Send for RawPtrHolder {}
 unsafe impl Sync for RawPtrHolder {}
+
+#[cfg(test)]
+mod tests {
+    use crate::phys_world::{all_physics_manager::PhysicsManager, scenario::Scenario};
+
+    use super::EditInput;
+
+    fn checksum_after(scenario: Scenario, max_threads: usize, ticks: u32) -> u64 {
+        let mut physics_manager = PhysicsManager::new();
+        physics_manager.chunk_manager.seed = 3;
+        physics_manager.chunk_manager.max_threads = max_threads;
+        scenario.build(&mut physics_manager.chunk_manager);
+
+        for _ in 0..ticks {
+            physics_manager.step(&EditInput::default());
+            physics_manager.clear_body_bytes();
+        }
+        physics_manager.chunk_manager.world_checksum()
+    }
+
+    #[test]
+    fn same_world_for_any_amount_of_threads() {
+        for scenario in [Scenario::Bonfire, Scenario::LavaFlow, Scenario::SandPile, Scenario::Avalanche] {
+            let checksum = checksum_after(scenario, 1, 60);
+            for max_threads in [2, 4, 0] {
+                assert_eq!(checksum, checksum_after(scenario, max_threads, 60), "{} with {} threads", scenario.name(), max_threads);
+            }
+        }
+    }
+}
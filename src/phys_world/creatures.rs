@@ -0,0 +1,194 @@
+use super::{
+    base_movement::{get, set, set_colliders_dirty, swap},
+    chunk::{Grid, MovData},
+    element::{air_element, dirt_element, smoke_element, Action, Cell, Creature, CreatureKind, CreatureMode, ElColliderType, Element, State},
+    element_registry::registry,
+};
+
+// INFO: Chances per frame that a creature takes a step, falling creatures fall every frame
+const ANT_SPEED: f32 = 0.3;
+const WORM_SPEED: f32 = 0.1;
+// INFO: Chance per step that an ant starts digging and that a digging ant stops again
+const DIG_CHANCE: f32 = 0.01;
+const STOP_DIG_CHANCE: f32 = 0.1;
+// INFO: Chance per step that a worm turns around or changes the height it burrows at
+const WORM_TURN_CHANCE: f32 = 0.05;
+const WORM_WIGGLE_CHANCE: f32 = 0.3;
+// INFO: Food (the lifetime) one step costs, one eaten cell gives and that makes a creature split in two
+const STEP_COST: i32 = 1;
+const FOOD: i32 = 400;
+const SPLIT_FOOD: i32 = 4000;
+// INFO: Creatures die at this temperature, even before anything around them burns
+const DEATH_TEMPERATURE: f32 = 80.;
+
+/*
+    INFO: Ants and worms are cells with a tiny state machine in their Creature action, the lifetime is the food they have left.
+    Ants walk along any surface (including walls and ceilings), fall when they lose grip and sometimes dig down into soil,
+    worms burrow through soil and only crawl over it when they got out. Both eat what is edible, split when they ate a lot
+    and starve into dirt. Fire, lava and heat kill them.
+    Creatures move with swap like every other cell, so they walk into neighboring chunks the same way
+*/
+pub fn update(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, creature: Creature) {
+    let (x, y) = (i as i32, j as i32);
+    *mov_dt.keep_active = true;
+    mov_dt.dirty_rect.set_temp(i, j);
+
+    let neighbors = [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)];
+    let deadly = neighbors.iter().any(|&(nx, ny)| {
+        let other = get(nx, ny, future_grid, mov_dt).element;
+        other == Element::Fire || other == Element::Lava
+    });
+    if deadly || future_grid[i][j].temperature >= DEATH_TEMPERATURE {
+        set(x, y, future_grid, mov_dt, smoke_element());
+        return;
+    }
+
+    if future_grid[i][j].lifetime <= 0 {
+        set(x, y, future_grid, mov_dt, dirt_element());
+        return;
+    }
+
+    for (nx, ny) in neighbors {
+        if registry().get(get(nx, ny, future_grid, mov_dt).element).edible {
+            set(nx, ny, future_grid, mov_dt, air_element());
+            future_grid[i][j].lifetime += FOOD;
+            break;
+        }
+    }
+
+    if future_grid[i][j].lifetime >= SPLIT_FOOD {
+        if let Some(&(nx, ny)) = neighbors.iter().find(|&&(nx, ny)| is_free(&get(nx, ny, future_grid, mov_dt))) {
+            future_grid[i][j].lifetime /= 2;
+            let child = future_grid[i][j];
+            set(nx, ny, future_grid, mov_dt, child);
+        }
+    }
+
+    let target = match creature.kind {
+        CreatureKind::Ant => ant(future_grid, i, j, mov_dt, creature),
+        CreatureKind::Worm => worm(future_grid, i, j, mov_dt, creature),
+    };
+    if let Some((tx, ty)) = target {
+        future_grid[i][j].lifetime -= STEP_COST;
+        move_to(future_grid, i, j, tx, ty, mov_dt);
+    }
+}
+
+fn set_state(future_grid: &mut Grid, i: usize, j: usize, creature: Creature) {
+    future_grid[i][j].action = Some(Action::Creature(creature));
+}
+
+fn is_free(cell: &Cell) -> bool {
+    cell.state == State::Gas && cell.element != Element::Fire
+}
+
+fn is_diggable(cell: &Cell) -> bool {
+    registry().get(cell.element).diggable
+}
+
+// INFO: Something to hold on to, creatures do not hold on to each other
+fn is_grip(cell: &Cell) -> bool {
+    !is_free(cell) && cell.state != State::Liquid && !matches!(cell.action, Some(Action::Creature(_)))
+}
+
+// INFO: A free cell with something to hold on to around it, not counting the creature that wants to move there
+fn is_walkable(x: i32, y: i32, from: (i32, i32), future_grid: &mut Grid, mov_dt: &mut MovData) -> bool {
+    if !is_free(&get(x, y, future_grid, mov_dt)) {
+        return false;
+    }
+    [(x, y + 1), (x, y - 1), (x - 1, y), (x + 1, y)]
+        .into_iter()
+        .any(|(nx, ny)| (nx, ny) != from && is_grip(&get(nx, ny, future_grid, mov_dt)))
+}
+
+fn ant(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, mut ant: Creature) -> Option<(i32, i32)> {
+    let (x, y) = (i as i32, j as i32);
+    let facing = ant.facing as i32;
+
+    let holding = [(x, y + 1), (x, y - 1), (x - 1, y), (x + 1, y)].into_iter().any(|(nx, ny)| is_grip(&get(nx, ny, future_grid, mov_dt)));
+    if !holding {
+        ant.mode = CreatureMode::Fall;
+        set_state(future_grid, i, j, ant);
+        return is_free(&get(x, y + 1, future_grid, mov_dt)).then_some((x, y + 1));
+    }
+    if ant.mode == CreatureMode::Fall {
+        ant.mode = CreatureMode::Walk;
+        set_state(future_grid, i, j, ant);
+    }
+
+    if mov_dt.rng.f32() >= ANT_SPEED {
+        return None;
+    }
+
+    if ant.mode == CreatureMode::Dig {
+        let down = (x + facing, y + 1);
+        if mov_dt.rng.f32() >= STOP_DIG_CHANCE && is_diggable(&get(down.0, down.1, future_grid, mov_dt)) {
+            return Some(down);
+        }
+        ant.mode = CreatureMode::Walk;
+        set_state(future_grid, i, j, ant);
+    } else if is_diggable(&get(x, y + 1, future_grid, mov_dt)) && mov_dt.rng.f32() < DIG_CHANCE {
+        ant.mode = CreatureMode::Dig;
+        set_state(future_grid, i, j, ant);
+        return None;
+    }
+
+    // INFO: Ahead, a step up, a step down, then up or down a wall, otherwise turn around
+    let from = (x, y);
+    for (tx, ty) in [(x + facing, y), (x + facing, y - 1), (x + facing, y + 1), (x, y - 1), (x, y + 1)] {
+        if is_walkable(tx, ty, from, future_grid, mov_dt) {
+            return Some((tx, ty));
+        }
+    }
+    ant.facing = -ant.facing;
+    set_state(future_grid, i, j, ant);
+    None
+}
+
+fn worm(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, mut worm: Creature) -> Option<(i32, i32)> {
+    let (x, y) = (i as i32, j as i32);
+
+    let below = get(x, y + 1, future_grid, mov_dt);
+    let buried = [(x, y - 1), (x - 1, y), (x + 1, y)].into_iter().any(|(nx, ny)| is_diggable(&get(nx, ny, future_grid, mov_dt)));
+    if is_free(&below) && !buried {
+        worm.mode = CreatureMode::Fall;
+        set_state(future_grid, i, j, worm);
+        return Some((x, y + 1));
+    }
+    if worm.mode == CreatureMode::Fall {
+        worm.mode = CreatureMode::Dig;
+        set_state(future_grid, i, j, worm);
+    }
+
+    if mov_dt.rng.f32() >= WORM_SPEED {
+        return None;
+    }
+
+    if mov_dt.rng.f32() < WORM_TURN_CHANCE {
+        worm.facing = -worm.facing;
+        set_state(future_grid, i, j, worm);
+    }
+    let facing = worm.facing as i32;
+    let dy = if mov_dt.rng.f32() < WORM_WIGGLE_CHANCE { if mov_dt.rng.bool() { 1 } else { -1 } } else { 0 };
+
+    for (tx, ty) in [(x + facing, y + dy), (x + facing, y), (x, y + dy)] {
+        let surface = is_walkable(tx, ty, (x, y), future_grid, mov_dt) && is_grip(&get(tx, ty + 1, future_grid, mov_dt));
+        if surface || is_diggable(&get(tx, ty, future_grid, mov_dt)) {
+            return Some((tx, ty));
+        }
+    }
+
+    worm.facing = -worm.facing;
+    set_state(future_grid, i, j, worm);
+    None
+}
+
+// INFO: Swaps the creature with the cell it moves to, the dug soil ends up behind it
+fn move_to(future_grid: &mut Grid, i: usize, j: usize, x: i32, y: i32, mov_dt: &mut MovData) {
+    let target = get(x, y, future_grid, mov_dt);
+    if target.collider_type == ElColliderType::Chunk || future_grid[i][j].collider_type == ElColliderType::Chunk {
+        set_colliders_dirty(i as i32, j as i32, mov_dt);
+        set_colliders_dirty(x, y, mov_dt);
+    }
+    swap(future_grid, i, j, x, y, mov_dt);
+}
@@ -1,17 +1,29 @@
-use notan::math::Vec2;
+use glam::Vec2;
 
 use crate::{phys_world::base_movement::*, phys_world::chunk::{Grid, MovData, self}};
 
-use super::element::{air_element, Element, firework_ember_element, fire_element};
+use super::{element::{air_element, el_from_enum, Element, State, firework_ember_element, fire_element, smoke_element}, element_actions::{has_oxygen, use_oxygen}, element_registry::registry, erosion::erode};
 
+/*
+	INFO: Powders with room below them are free falling and knock the powders next to them loose, unless their inertia holds them.
+	Only free falling powders slide down the sides, once they landed they stop with the chance of their friction every frame.
+	Powders with a lot of friction and inertia pile up steep and stay put, the others keep flowing
+*/
 #[inline]
 pub fn falling_sand(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
 	apply_gravity(f_grid, i, j, mov_dt);
-	if !downward(f_grid, i, j, mov_dt) {
-		if !apply_velocity(f_grid, i, j, mov_dt) {
-			if !downward_sides(f_grid, i, j, mov_dt) {
-				return false;
-			}
+
+	if get(i as i32, j as i32 + 1, f_grid, mov_dt).density < f_grid[i][j].density {
+		f_grid[i][j].free_falling = true;
+		knock_loose(i as i32 - 1, j as i32, f_grid, mov_dt);
+		knock_loose(i as i32 + 1, j as i32, f_grid, mov_dt);
+	}
+
+	if !downward(f_grid, i, j, mov_dt) && !apply_velocity(f_grid, i, j, mov_dt) {
+		let friction = registry().get(f_grid[i][j].element).friction;
+		if !f_grid[i][j].free_falling || mov_dt.rng.f32() < friction || !downward_sides(f_grid, i, j, mov_dt) {
+			f_grid[i][j].free_falling = false;
+			return false;
 		}
 	}
 
@@ -19,13 +31,39 @@ pub fn falling_sand(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData)
 	true
 }
 
+fn knock_loose(x: i32, y: i32, f_grid: &mut Grid, mov_dt: &mut MovData) {
+	let mut cell = get(x, y, f_grid, mov_dt);
+	if cell.state != State::Powder || cell.free_falling {
+		return;
+	}
+	if mov_dt.rng.f32() >= registry().get(cell.element).inertia {
+		cell.free_falling = true;
+		set(x, y, f_grid, mov_dt, cell);
+	}
+}
+
 #[inline]
 pub fn liquid_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
+	let def = registry().get(f_grid[i][j].element);
+
+	/*
+		INFO: Viscous liquids that rest on something skip frames, so they spread slower but still fall like the others.
+		A skipped cell keeps the chunk awake as long as it could still move to a side
+	*/
+	let density = f_grid[i][j].density;
+	if def.viscosity > 0. && get(i as i32, j as i32 + 1, f_grid, mov_dt).density >= density && mov_dt.rng.f32() < def.viscosity {
+		if get(i as i32 - 1, j as i32, f_grid, mov_dt).density < density || get(i as i32 + 1, j as i32, f_grid, mov_dt).density < density {
+			*mov_dt.keep_active = true;
+			mov_dt.dirty_rect.set_temp(i, j);
+		}
+		return false;
+	}
+
 	apply_gravity(f_grid, i, j, mov_dt);
 
 	let down_density = get(i as i32, j as i32 + 1, f_grid, mov_dt).density;
 
-	if down_density >= f_grid[i][j].density && f_grid[i][j].velocity.x.abs() <= 7. {
+	if down_density >= f_grid[i][j].density && f_grid[i][j].velocity.x.abs() <= def.max_dispersion {
 		let mut left = f_grid[i][j].velocity.x < 0.;
 		let mut right = f_grid[i][j].velocity.x > 0.;
 
@@ -34,13 +72,13 @@ pub fn liquid_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovDa
 			right = get(i as i32 + 1, j as i32, f_grid, mov_dt).density < f_grid[i][j].density;
 			
 			if left && right {
-				let rand = fastrand::bool();
+				let rand = mov_dt.rng.bool();
 				left = rand;
 				right = !rand;
 			}
 		}
 
-		let acc = 3. * f_grid[i][j].drag;
+		let acc = def.dispersion * f_grid[i][j].drag;
 		if right {
 			if f_grid[i][j].velocity.x < 0. {
 				f_grid[i][j].velocity.x = 0.;
@@ -54,6 +92,8 @@ pub fn liquid_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovDa
 		}
 	}
 
+	erode(f_grid, i, j, mov_dt);
+
 	if apply_velocity(f_grid, i, j, mov_dt) {
 		*mov_dt.keep_active = true;
 
@@ -67,6 +107,10 @@ pub fn liquid_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovDa
 pub fn gas_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
 	let up_density = get(i as i32, j as i32 - 1, f_grid, mov_dt).density;
 
+	if dissipate(f_grid, i, j, mov_dt, up_density) {
+		return true;
+	}
+
 	if f_grid[i][j].velocity.y > -1.75 && up_density < f_grid[i][j].density {
 		f_grid[i][j].velocity.y += -0.5;
 	} else if up_density >= f_grid[i][j].density && f_grid[i][j].velocity.x.abs() <= 2.5 {
@@ -78,7 +122,7 @@ pub fn gas_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData)
 			right = get(i as i32 + 1, j as i32, f_grid, mov_dt).density < f_grid[i][j].density;
 			
 			if left && right {
-				let rand = fastrand::bool();
+				let rand = mov_dt.rng.bool();
 				left = rand;
 				right = !rand;
 			}
@@ -100,16 +144,67 @@ pub fn gas_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData)
 	false
 }
 
+/*
+	INFO: Gases with a half life disappear over time while nothing is above them, gases that condense turn into their liquid once they spent enough frames
+	in a row under a ceiling (or more of the same gas). The lifetime counts those frames,
+	the half life is a chance every frame and does not count down. Both keep the chunk awake until they are gone
+*/
+#[inline]
+fn dissipate(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, up_density: f32) -> bool {
+	let def = registry().get(f_grid[i][j].element);
+	if def.half_life.is_none() && def.condenses.is_none() {
+		return false;
+	}
+
+	*mov_dt.keep_active = true;
+	mov_dt.dirty_rect.set_temp(i, j);
+
+	// INFO: Only gases that are out in the open thin out, smoke trapped in a sealed room stays and does not give the air back
+	if let Some(half_life) = def.half_life {
+		if up_density < f_grid[i][j].density && mov_dt.rng.f32() < 1. - 0.5_f32.powf(1. / half_life) {
+			set(i as i32, j as i32, f_grid, mov_dt, air_element());
+			return true;
+		}
+	}
+
+	if let Some((frames, into)) = def.condenses {
+		if up_density >= f_grid[i][j].density {
+			f_grid[i][j].lifetime = f_grid[i][j].lifetime.max(0) + 1;
+			if f_grid[i][j].lifetime >= frames {
+				set(i as i32, j as i32, f_grid, mov_dt, el_from_enum(into));
+				return true;
+			}
+		} else {
+			// INFO: Out from under the ceiling it starts counting again
+			f_grid[i][j].lifetime = -1;
+		}
+	}
+
+	false
+}
+
 #[inline]
 pub fn fire_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
-	let rand = fastrand::i32(2..8);
+	// INFO: Chance per frame that a flame uses up the oxygen of a neighbor, which turns into smoke, so sealed in fires choke themselves
+	const OXYGEN_USE: f32 = 0.05;
+	// INFO: Flames without oxygen around them burn out this many times faster
+	const SMOTHER_SPEED: i32 = 4;
+
+	let rand = mov_dt.rng.i32(2..8);
 	f_grid[i][j].lifetime -= rand;
 
 	*mov_dt.keep_active = true;
 
+	if !has_oxygen(f_grid, i, j, mov_dt) {
+		f_grid[i][j].lifetime -= rand * (SMOTHER_SPEED - 1);
+	} else if mov_dt.rng.f32() < OXYGEN_USE {
+		use_oxygen(f_grid, i, j, mov_dt);
+	}
+
+	// INFO: Burnt out flames go out into smoke, the oxygen they burnt does not come back
 	if f_grid[i][j].lifetime <= 0 {
-		f_grid[i][j] = air_element();
-		chunk::update_byte(mov_dt.bytes, i, j, &[0, 0, 0, 0]);
+		f_grid[i][j] = smoke_element();
+		chunk::update_byte(mov_dt.bytes, i, j, &f_grid[i][j].color);
 		return true;
 	}
 
@@ -122,8 +217,6 @@ pub fn fire_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData
 	f_grid[i][j].color[3] = (f_grid[i][j].color[3] as f32 - (rand as f32).powf(2.)).clamp(220., 255.) as u8;
 	chunk::update_byte(mov_dt.bytes, i, j, &f_grid[i][j].color);
 
-	spread_fire(f_grid, i, j, mov_dt);
-	
 	if !apply_velocity(f_grid, i, j, mov_dt) {
 		mov_dt.dirty_rect.set_temp(i, j);
 	}
@@ -135,13 +228,13 @@ pub fn fire_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData
 pub fn firework_shell_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
 	*mov_dt.keep_active = true;
 
-	let rand = fastrand::i32(2..7);
+	let rand = mov_dt.rng.i32(2..7);
 	if f_grid[i][j].lifetime <= 0 {
 		f_grid[i][j] = air_element();
 		chunk::update_byte(mov_dt.bytes, i, j, &[0, 0, 0, 0]);
 
-		let size = fastrand::i32(30..=70);
-		let density = fastrand::i32(4..=8) as f32;
+		let size = mov_dt.rng.i32(30..=70);
+		let density = mov_dt.rng.i32(4..=8) as f32;
 		for x in -size..=size {
 			for y in -size..=size {
 				let dist = Vec2::new(x as f32, y as f32).length();
@@ -151,15 +244,15 @@ pub fn firework_shell_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &m
 
 				let vel = Vec2::new(x as f32, y as f32).normalize() * 5.;
 				let mut temp_el = get(i as i32 + x, j as i32 + y, f_grid, mov_dt);
-				temp_el.velocity = vel.clone();
+				temp_el.velocity = vel;
 				
 				set(i as i32 + x, j as i32 + y, f_grid, mov_dt, temp_el);
 				
-				if fastrand::i32(1..=4) == 4 {
+				if mov_dt.rng.i32(1..=4) == 4 {
 					let check_el = get(i as i32 + x, j as i32 + y, f_grid, mov_dt);
 					if check_el.element == Element::Air {
 						let mut firework = firework_ember_element();
-						firework.velocity = vel.clone();
+						firework.velocity = vel;
 						firework.density = density;
 						firework.lifetime = 130;
 					
@@ -192,7 +285,7 @@ pub fn firework_shell_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &m
 
 #[inline]
 pub fn firework_ember_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
-	let rand = fastrand::i32(2..8);
+	let rand = mov_dt.rng.i32(2..8);
 	f_grid[i][j].lifetime -= rand;
 
 	*mov_dt.keep_active = true;
@@ -223,8 +316,6 @@ pub fn firework_ember_movement(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &m
 	}
 	chunk::update_byte(mov_dt.bytes, i, j, &f_grid[i][j].color);
 
-	spread_fire(f_grid, i, j, mov_dt);
-	
 	apply_velocity(f_grid, i, j, mov_dt);
 	mov_dt.dirty_rect.set_temp(i, j);
 
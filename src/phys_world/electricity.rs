@@ -0,0 +1,85 @@
+use super::{
+    base_movement::{get, get_new_element_coord, get_wanted_chunk},
+    chunk::{self, in_bound, Grid, MovData},
+    element_registry::registry,
+    heat,
+};
+
+// INFO: Charge a cell gets when it is sparked, it sparks while the charge is above REFRACTORY and counts down one per frame
+pub const SPARK: u8 = 4;
+// INFO: Frames a cell can not be sparked again after it sparked, this is what makes sparks run along a wire instead of back and forth
+pub const REFRACTORY: u8 = 3;
+const SPARK_COLOR: [u8; 4] = [255, 244, 150, 255];
+
+/*
+    INFO: Sparks spread to the conductors around them and heat the flammable elements around them up to their ignition temperature.
+    Only cells that were sparking at the start of the frame spread and only cells that had no charge left at the start of the frame
+    can be sparked, so a spark moves one cell per frame no matter in which order the cells get updated
+*/
+pub fn handle_electricity(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
+    if future_grid[i][j].charge == 0 {
+        return;
+    }
+    let previous = mov_dt.grid[i][j].charge;
+
+    *mov_dt.keep_active = true;
+    mov_dt.dirty_rect.set_temp(i, j);
+
+    if previous == 0 {
+        return;
+    }
+
+    if previous > REFRACTORY {
+        for (x, y) in [(i as i32, j as i32 - 1), (i as i32, j as i32 + 1), (i as i32 - 1, j as i32), (i as i32 + 1, j as i32)] {
+            let other = get(x, y, future_grid, mov_dt);
+            let def = registry().get(other.element);
+            if def.conducts_electricity {
+                spark(x, y, future_grid, mov_dt);
+            } else if let Some(ignition) = def.ignition {
+                heat::add_temperature(x, y, future_grid, mov_dt, (ignition - other.temperature).max(0.));
+            }
+        }
+    }
+
+    future_grid[i][j].charge = previous - 1;
+    if future_grid[i][j].charge == REFRACTORY {
+        chunk::update_byte(mov_dt.bytes, i, j, &future_grid[i][j].color);
+    }
+}
+
+// INFO: For batteries, sparks every conductor around them that is not sparking or recovering from a spark already
+pub fn power(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
+    for (x, y) in [(i as i32, j as i32 - 1), (i as i32, j as i32 + 1), (i as i32 - 1, j as i32), (i as i32 + 1, j as i32)] {
+        if registry().get(get(x, y, future_grid, mov_dt).element).conducts_electricity {
+            spark(x, y, future_grid, mov_dt);
+        }
+    }
+
+    *mov_dt.keep_active = true;
+    mov_dt.dirty_rect.set_temp(i, j);
+}
+
+pub fn spark(i: i32, j: i32, f_grid: &mut Grid, mov_dt: &mut MovData) {
+    if in_bound(i, j) {
+        let cell = &mut f_grid[i as usize][j as usize];
+        if cell.charge != 0 || mov_dt.grid[i as usize][j as usize].charge != 0 {
+            return;
+        }
+        cell.charge = SPARK;
+        chunk::update_byte(mov_dt.bytes, i as usize, j as usize, &SPARK_COLOR);
+        mov_dt.dirty_rect.set_temp(i as usize, j as usize);
+    } else {
+        let wanted_chunk = get_wanted_chunk(mov_dt.index, i, j);
+
+        if let Some(chunk) = mov_dt.chunks.get_mut(&wanted_chunk) {
+            let (x, y) = get_new_element_coord(i, j);
+            let cell = &mut chunk.grid[x as usize][y as usize];
+            if cell.charge != 0 {
+                return;
+            }
+            cell.charge = SPARK;
+            chunk::update_byte(&mut chunk.bytes, x as usize, y as usize, &SPARK_COLOR);
+            mov_dt.wake_ups.push((wanted_chunk, (x as usize, y as usize)));
+        }
+    }
+}
@@ -1,4 +1,9 @@
-use notan::math::Vec2;
+use std::fmt;
+
+use glam::Vec2;
+use serde::Deserialize;
+
+use super::element_registry::registry;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Cell {
@@ -10,363 +15,154 @@ pub struct Cell {
     pub velocity: Vec2,
     pub drag: f32,
     pub lifetime: i32,
+    pub temperature: f32,
+    // INFO: Frames left of a spark going through the cell, see electricity.rs
+    pub charge: u8,
+    // INFO: Water cells a powder soaked up, see wetness.rs
+    pub wetness: u8,
+    // INFO: Powders only slide down the sides while they are free falling, see el_movement::falling_sand
+    pub free_falling: bool,
     pub collider_type: ElColliderType
 }
 
 pub fn air_element() -> Cell {
-    Cell {
-        element: Element::Air,
-        action: None,
-        density: 0.,
-        state: State::Gas,
-        color: [0, 0, 0, 0],
-        velocity: Vec2::ZERO,
-        drag: 0.95,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Air)
 }
 
 pub fn solid_element() -> Cell {
-    Cell {
-        element: Element::Solid,
-        action: None,
-        density: 100.,
-        state: State::Solid,
-        color: [69, 62, 66, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.,
-        lifetime: -1,
-        collider_type: ElColliderType::Chunk
-    }
+    el_from_enum(Element::Solid)
 }
 
 pub fn wood_element() -> Cell {
-    Cell {
-        element: Element::Wood,
-        action: None,
-        density: 100.,
-        state: State::Solid,
-        color: [111, 83, 57, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.,
-        lifetime: -1,
-        collider_type: ElColliderType::Chunk
-    }
+    el_from_enum(Element::Wood)
 }
 
 pub fn coal_element() -> Cell {
-    Cell {
-        element: Element::Coal,
-        action: None,
-        density: 100.,
-        state: State::Solid,
-        color: [42, 42, 42, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.,
-        lifetime: -1,
-        collider_type: ElColliderType::Chunk
-    }
+    el_from_enum(Element::Coal)
 }
 
 pub fn sand_element() -> Cell {
-    Cell {
-        element: Element::Sand,
-        action: None,
-        density: 60.,
-        state: State::Powder,
-        color: [243, 239, 118, 255],
-        velocity: Vec2::ZERO,
-        drag: 1.0,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Sand)
 }
 
 pub fn dirt_element() -> Cell {
-    Cell {
-        element: Element::Dirt,
-        action: None,
-        density: 60.,
-        state: State::Powder,
-        color: [76, 57, 32, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.9,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Dirt)
 }
 
 pub fn sawdust_element() -> Cell {
-    Cell {
-        element: Element::SawDust,
-        action: None,
-        density: 40.,
-        state: State::Powder,
-        color: [181, 137, 100, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.9,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::SawDust)
 }
 
 pub fn water_element() -> Cell {
-    Cell {
-        element: Element::Water,
-        action: None,
-        density: 50.,
-        state: State::Liquid,
-        color: [55, 46, 229, 175],
-        velocity: Vec2::ZERO,
-        drag: 0.4,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Water)
 }
 
 pub fn petrol_element() -> Cell {
-    Cell {
-        element: Element::Petrol,
-        action: None,
-        density: 45.,
-        state: State::Liquid,
-        color: [0, 95, 106, 175],
-        velocity: Vec2::ZERO,
-        drag: 0.4,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Petrol)
 }
 
 pub fn smoke_element() -> Cell {
-    Cell {
-        element: Element::Smoke,
-        action: None,
-        density: 4.,
-        state: State::Gas,
-        color: [42, 42, 42, 220],
-        velocity: Vec2::ZERO,
-        drag: 0.95,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Smoke)
 }
 
 pub fn steam_element() -> Cell {
-    Cell {
-        element: Element::Steam,
-        action: None,
-        density: 2.,
-        state: State::Gas,
-        color: [143, 159, 234, 140],
-        velocity: Vec2::ZERO,
-        drag: 0.95,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Steam)
 }
 
 pub fn fire_element() -> Cell {
-    Cell {
-        element: Element::Fire,
-        action: None,
-        density: 4.,
-        state: State::Plasma,
-        color: [255, 170, 0, 220],
-        velocity: Vec2::ZERO,
-        drag: 1.,
-        lifetime: 50,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Fire)
 }
 
 pub fn methane_element() -> Cell {
-    Cell {
-        element: Element::Methane,
-        action: None,
-        density: 3.,
-        state: State::Gas,
-        color: [130, 171, 41, 140],
-        velocity: Vec2::ZERO,
-        drag: 0.95,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Methane)
 }
 
 pub fn lava_element() -> Cell {
-    Cell {
-        element: Element::Lava,
-        action: Some(Action::Burn),
-        density: 120.,
-        state: State::Liquid,
-        color: [255, 102, 0, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.1,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Lava)
 }
 
 pub fn source_element() -> Cell {
-    Cell {
-        element: Element::Source,
-        action: Some(Action::EmitSource(Element::Air)),
-        density: 100.,
-        state: State::Solid,
-        color: [252, 186, 3, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.,
-        lifetime: -1,
-        collider_type: ElColliderType::Chunk
-    }
+    el_from_enum(Element::Source)
 }
 
 pub fn gravel_element() -> Cell {
-    Cell {
-        element: Element::Gravel,
-        action: None,
-        density: 130.,
-        state: State::Powder,
-        color: [83, 84, 78, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.9,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Gravel)
 }
 
 pub fn soliddirt_element() -> Cell {
-    Cell {
-        element: Element::SolidDirt,
-        action: None,
-        density: 100.,
-        state: State::Solid,
-        color: [76, 57, 32, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.,
-        lifetime: -1,
-        collider_type: ElColliderType::Chunk
-    }
+    el_from_enum(Element::SolidDirt)
 }
 
 pub fn grass_element() -> Cell {
-    Cell {
-        element: Element::Grass,
-        action: Some(Action::Grow),
-        density: 60.,
-        state: State::Solid,
-        color: [19, 109, 21, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Grass)
 }
 
 pub fn brick_element() -> Cell {
-    Cell {
-        element: Element::Brick,
-        action: None,
-        density: 100.,
-        state: State::Solid,
-        color: [156, 89, 89, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.,
-        lifetime: -1,
-        collider_type: ElColliderType::Chunk
-    }
+    el_from_enum(Element::Brick)
 }
 
 pub fn snow_element() -> Cell {
-    Cell {
-        element: Element::Snow,
-        action: None,
-        density: 40.,
-        state: State::Powder,
-        color: [255, 255, 255, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.9,
-        lifetime: -1,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::Snow)
 }
 
-
 pub fn ice_element() -> Cell {
-    Cell {
-        element: Element::Ice,
-        action: None,
-        density: 100.,
-        state: State::Solid,
-        color: [154, 176, 221, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.,
-        lifetime: -1,
-        collider_type: ElColliderType::Chunk
-    }
+    el_from_enum(Element::Ice)
 }
 
 pub fn firework_shell_element() -> Cell {
-    Cell {
-        element: Element::FireworkShell,
-        action: None,
-        density: 30.,
-        state: State::Powder,
-        color: [200, 200, 200, 255],
-        velocity: Vec2::ZERO,
-        drag: 0.,
-        lifetime: 150,
-        collider_type: ElColliderType::None
-    }
+    el_from_enum(Element::FireworkShell)
 }
 
 pub fn firework_ember_element() -> Cell {
-    Cell {
-        element: Element::FireworkEmber,
-        action: None,
-        density: 4.,
-        state: State::Plasma,
-        color: [14, 8, 184, 0],
-        velocity: Vec2::ZERO,
-        drag: 1.,
-        lifetime: 100,
-        collider_type: ElColliderType::None
-    }
-}
-
-
-
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub enum Element {
-    Air,
-    Solid,
-    Sand,
-    SawDust,
-    Water,
-    Steam,
-    Smoke,
-    Dirt,
-    Fire,
-    Wood,
-    Coal,
-    Methane,
-    Petrol,
-    Lava,
-    Source,
-    Gravel,
-    SolidDirt,
-    Grass,
-    Brick,
-    Snow,
-    Ice,
-    FireworkShell,
-    FireworkEmber
-}
-
-#[derive(Clone, Copy, Debug, PartialEq)]
+    el_from_enum(Element::FireworkEmber)
+}
+
+/*
+    INFO: Elements are ids into the element registry, which is loaded from the definitions file (assets/elements.ron).
+    The built-in elements have fixed ids so the simulation code can refer to them, they keep the names they had as enum variants
+*/
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Element(pub u16);
+
+#[allow(non_upper_case_globals)]
+impl Element {
+    pub const Air: Element = Element(0);
+    pub const Solid: Element = Element(1);
+    pub const Sand: Element = Element(2);
+    pub const SawDust: Element = Element(3);
+    pub const Water: Element = Element(4);
+    pub const Steam: Element = Element(5);
+    pub const Smoke: Element = Element(6);
+    pub const Dirt: Element = Element(7);
+    pub const Fire: Element = Element(8);
+    pub const Wood: Element = Element(9);
+    pub const Coal: Element = Element(10);
+    pub const Methane: Element = Element(11);
+    pub const Petrol: Element = Element(12);
+    pub const Lava: Element = Element(13);
+    pub const Source: Element = Element(14);
+    pub const Gravel: Element = Element(15);
+    pub const SolidDirt: Element = Element(16);
+    pub const Grass: Element = Element(17);
+    pub const Brick: Element = Element(18);
+    pub const Snow: Element = Element(19);
+    pub const Ice: Element = Element(20);
+    pub const FireworkShell: Element = Element(21);
+    pub const FireworkEmber: Element = Element(22);
+}
+
+// INFO: Names of the built-in elements, in the order of their ids
+pub const BUILTIN_ELEMENTS: [&str; 23] = [
+    "Air", "Solid", "Sand", "SawDust", "Water", "Steam", "Smoke", "Dirt", "Fire", "Wood", "Coal", "Methane", "Petrol", "Lava", "Source", "Gravel", "SolidDirt", "Grass", "Brick", "Snow", "Ice", "FireworkShell", "FireworkEmber"
+];
+
+impl fmt::Debug for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", registry().get(*self).name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
 pub enum State {
     Solid,
     Powder,
@@ -377,38 +173,79 @@ pub enum State {
 
 #[derive(Clone, Copy, Debug)]
 pub enum Action {
-    Burn, EmitSource(Element), Grow
+    EmitSource(Element), Grow(Growth), Corrode, Power,
+    // INFO: Logic cells, see logic.rs. Doors and pistons know the element of their door and arm
+    Sense(Element), Gate(LogicGate), Door(Element), Piston(Element),
+    Creature(Creature)
 }
 
-pub fn el_from_enum(element: Element) -> Cell {
-    match element {
-        Element::Air => air_element(),
-        Element::Solid => solid_element(),
-        Element::Sand => sand_element(),
-        Element::SawDust => sawdust_element(),
-        Element::Water => water_element(),
-        Element::Steam => steam_element(),
-        Element::Smoke => smoke_element(),
-        Element::Dirt => dirt_element(),
-        Element::Fire => fire_element(),
-        Element::Wood => wood_element(),
-        Element::Coal => coal_element(),
-        Element::Methane => methane_element(),
-        Element::Petrol => petrol_element(),
-        Element::Lava => lava_element(),
-        Element::Source => source_element(),
-        Element::Gravel => gravel_element(),
-        Element::SolidDirt => soliddirt_element(),
-        Element::Grass => grass_element(),
-        Element::Brick => brick_element(),
-        Element::Snow => snow_element(),
-        Element::Ice => ice_element(),
-        Element::FireworkShell => firework_shell_element(),
-        Element::FireworkEmber => firework_ember_element(),
+// INFO: The rule sets of plants.rs, branches know which side they grow to
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum Growth {
+    Grass,
+    Seed,
+    Tree,
+    Branch(i8),
+    Vine,
+}
+
+// INFO: The state of a creature cell, see creatures.rs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Creature {
+    pub kind: CreatureKind,
+    // INFO: -1 left, 1 right
+    pub facing: i8,
+    pub mode: CreatureMode,
+}
+
+impl Creature {
+    pub fn new(kind: CreatureKind) -> Self {
+        Self { kind, facing: 1, mode: CreatureMode::Walk }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum CreatureKind {
+    Ant,
+    Worm,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CreatureMode {
+    Walk,
+    Fall,
+    Dig,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum LogicGate {
+    And,
+    Or,
+    Not,
+    Delay,
+}
+
+// INFO: A fresh cell of the element, everything about it comes from the element's definition
+pub fn el_from_enum(element: Element) -> Cell {
+    let def = registry().get(element);
+    Cell {
+        element,
+        action: def.action,
+        density: def.density,
+        state: def.state,
+        color: def.color,
+        velocity: Vec2::ZERO,
+        drag: def.drag,
+        lifetime: def.lifetime,
+        temperature: def.temperature,
+        charge: 0,
+        wetness: def.wetness,
+        free_falling: true,
+        collider_type: def.collider_type
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
 pub enum ElColliderType {
     None,
     Chunk,
@@ -1,206 +1,239 @@
 use crate::{phys_world::element::*, phys_world::chunk::{Grid, in_bound, MovData}, phys_world::base_movement::*};
 
-use super::chunk;
-
-pub fn handle_actions(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, frame_count: u128) {
-    match future_grid[i][j].action {
-        Some(action) => 'action: {
-            match action {
-                Action::Burn => {
-                    let (lifetime, burn_element, emit_fire, darken, light_other) = get_flammable_info(&future_grid[i][j].element);
-                    if future_grid[i][j].lifetime == -1 {
-                        future_grid[i][j].lifetime = lifetime;
-                        if darken {
-                            future_grid[i][j].color[0] /= 2;
-                            future_grid[i][j].color[1] /= 2;
-                            future_grid[i][j].color[2] /= 2;
-                            chunk::update_byte(&mut mov_dt.bytes, i, j, &future_grid[i][j].color);
-                        }
-                    } else if future_grid[i][j].lifetime < 0 && future_grid[i][j].lifetime != -100 {
-                        set(i as i32, j as i32, future_grid, mov_dt, burn_element);
-                        *mov_dt.keep_active = true;
-                        mov_dt.dirty_rect.set_temp(i, j);
-                        *mov_dt.colliders_dirty = true;
-                        break 'action;
-                    }
+use super::{creatures, electricity, element_registry::{registry, Reaction}, explosion::detonate, logic, plants, pressure::{add_pressure, GAS_PRESSURE}};
 
-                    *mov_dt.keep_active = true;
-                    mov_dt.dirty_rect.set_temp(i, j);
+// INFO: Chance per frame that acid dissolves a neighbor without any acid resistance
+const CORRODE_RATE: f32 = 0.2;
+// INFO: Chance per frame that a burning cell sets one of its neighbors on fire if it is air
+const FLAME_CHANCE: f32 = 0.4;
 
-                    // INFO: We do this instead of fastrand for performace reasons
-                    let rand = (i as u128 + j as u128 + frame_count) as i32 % 10;
-                    if future_grid[i][j].lifetime != -100 {
-                        future_grid[i][j].lifetime -= rand;
-                        if future_grid[i][j].lifetime == -1 {
-                            future_grid[i][j].lifetime -= 1;
-                        }
-                    }
+// INFO: Burning needs a neighbor with oxygen (air), anything covered or sealed in goes out
+pub fn has_oxygen(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
+    let (x, y) = (i as i32, j as i32);
+    [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)]
+        .into_iter()
+        .any(|(nx, ny)| registry().get(get(nx, ny, future_grid, mov_dt).element).oxygen)
+}
 
-                    if light_other {
-                        spread_fire(future_grid, i, j, mov_dt);
-                    }
+// INFO: Burns the oxygen of a random neighbor, it turns into smoke
+pub fn use_oxygen(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
+    let (x, y) = (i as i32, j as i32);
+    let neighbors = [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)];
+    let start = mov_dt.rng.usize(..neighbors.len());
+    for k in 0..neighbors.len() {
+        let (nx, ny) = neighbors[(start + k) % neighbors.len()];
+        if registry().get(get(nx, ny, future_grid, mov_dt).element).oxygen {
+            set(nx, ny, future_grid, mov_dt, smoke_element());
+            return;
+        }
+    }
+}
 
-                    if emit_fire {
-                        match rand {
-                            0 => if get(i as i32, j as i32 - 1,  future_grid, mov_dt).element == Element::Air {
-                                set(i as i32, j as i32 - 1,  future_grid, mov_dt, fire_element());
-                            },
-                            1 => if get(i as i32 + 1, j as i32,  future_grid, mov_dt).element == Element::Air {
-                                set(i as i32 + 1, j as i32,  future_grid, mov_dt, fire_element());
-                            },
-                            2 => if get(i as i32, j as i32 + 1,  future_grid, mov_dt).element == Element::Air {
-                                set(i as i32, j as i32 + 1,  future_grid, mov_dt, fire_element());
-                            },
-                            3 => if get(i as i32 - 1, j as i32,  future_grid, mov_dt).element == Element::Air {
-                                set(i as i32 - 1, j as i32,  future_grid, mov_dt, fire_element());
-                            },
-                            _ => ()
+pub fn handle_actions(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
+    let Some(action) = future_grid[i][j].action else {
+        return;
+    };
+    'action: {
+        match action {
+            Action::EmitSource(emit_element) => {
+                let up = get(i as i32, j as i32 - 1,  future_grid, mov_dt);
+                let down = get(i as i32, j as i32 + 1,  future_grid, mov_dt);
+                let left = get(i as i32 - 1, j as i32,  future_grid, mov_dt);
+                let right = get(i as i32 + 1, j as i32,  future_grid, mov_dt);
+                match emit_element {
+                    Element::Air => {
+                        if up.state != State::Solid {
+                            future_grid[i][j].action = Some(Action::EmitSource(up.element));
+                        } else if down.state != State::Solid {
+                            future_grid[i][j].action = Some(Action::EmitSource(down.element));
+                        } else if left.state != State::Solid {
+                            future_grid[i][j].action = Some(Action::EmitSource(left.element));
+                        } else if right.state != State::Solid {
+                            future_grid[i][j].action = Some(Action::EmitSource(right.element));
                         }
-                    }
-                }
-                Action::EmitSource(emit_element) => {
-                    let up = get(i as i32, j as i32 - 1,  future_grid, mov_dt);
-                    let down = get(i as i32, j as i32 + 1,  future_grid, mov_dt);
-                    let left = get(i as i32 - 1, j as i32,  future_grid, mov_dt);
-                    let right = get(i as i32 + 1, j as i32,  future_grid, mov_dt);
-                    match emit_element {
-                        Element::Air => {
-                            if up.state != State::Solid {
-                                future_grid[i][j].action = Some(Action::EmitSource(up.element));
-                            } else if down.state != State::Solid {
-                                future_grid[i][j].action = Some(Action::EmitSource(down.element));
-                            } else if left.state != State::Solid {
-                                future_grid[i][j].action = Some(Action::EmitSource(left.element));
-                            } else if right.state != State::Solid {
-                                future_grid[i][j].action = Some(Action::EmitSource(right.element));
-                            }
-                        },
-                        _ => {
-                            if up.state == State::Gas {
-                                set(i as i32, j as i32 - 1,  future_grid, mov_dt, el_from_enum(emit_element));
-                            }
-                            if down.state == State::Gas {
-                                set(i as i32, j as i32 + 1,  future_grid, mov_dt, el_from_enum(emit_element));
-                            }
-                            if left.state == State::Gas {
-                                set(i as i32 - 1, j as i32,  future_grid, mov_dt, el_from_enum(emit_element));
-                            }
-                            if right.state == State::Gas {
-                                set(i as i32 + 1, j as i32,  future_grid, mov_dt, el_from_enum(emit_element));
-                            }
+                    },
+                    _ => {
+                        if up.state == State::Gas {
+                            set(i as i32, j as i32 - 1,  future_grid, mov_dt, el_from_enum(emit_element));
                         }
-                    }
-                },
-                // INFO: For grass
-                Action::Grow => {
-                    let up = get(i as i32, j as i32 - 1,  future_grid, mov_dt);
-                    let down = get(i as i32, j as i32 + 1,  future_grid, mov_dt);
-                    let left = get(i as i32 - 1, j as i32,  future_grid, mov_dt);
-                    let right = get(i as i32 + 1, j as i32,  future_grid, mov_dt);
-
-                    let up_right = get(i as i32 + 1, j as i32 - 1,  future_grid, mov_dt);
-                    let up_left = get(i as i32 - 1, j as i32 - 1,  future_grid, mov_dt);
-                    let down_right = get(i as i32 + 1, j as i32 + 1,  future_grid, mov_dt);
-                    let down_left = get(i as i32 - 1, j as i32 + 1,  future_grid, mov_dt);
-
-                    let mut active = false;
-                    if left.element == Element::Air && down_left.element == Element::SolidDirt {
-                        set(i as i32 - 1, j as i32, future_grid, mov_dt, grass_element());
-                        active = true;
-                    }
-                    if right.element == Element::Air && down_right.element == Element::SolidDirt {
-                        set(i as i32 + 1, j as i32, future_grid, mov_dt, grass_element());
-                        active = true;
-                    }
-                    if down.element == Element::SolidDirt {
-                        if down_right.element == Element::Air {
-                            set(i as i32 + 1, j as i32 + 1, future_grid, mov_dt, grass_element());
-                            active = true;
+                        if down.state == State::Gas {
+                            set(i as i32, j as i32 + 1,  future_grid, mov_dt, el_from_enum(emit_element));
                         }
-                        if down_left.element == Element::Air {
-                            set(i as i32 - 1, j as i32 + 1, future_grid, mov_dt, grass_element());
-                            active = true;
+                        if left.state == State::Gas {
+                            set(i as i32 - 1, j as i32,  future_grid, mov_dt, el_from_enum(emit_element));
+                        }
+                        if right.state == State::Gas {
+                            set(i as i32 + 1, j as i32,  future_grid, mov_dt, el_from_enum(emit_element));
                         }
                     }
-                    if down.element == Element::Air &&
-                       (down_right.element == Element::SolidDirt || down_left.element == Element::SolidDirt) {
-                            set(i as i32, j as i32 + 1, future_grid, mov_dt, grass_element());
-                            active = true;
-                    }
-                    if up.element == Element::Air &&
-                       (up_right.element == Element::SolidDirt || up_left.element == Element::SolidDirt) {
-                            set(i as i32, j as i32 - 1, future_grid, mov_dt, grass_element());
-                            active = true;
-                    }
-                    if right.element == Element::SolidDirt && up_right.element == Element::Air {
-                        set(i as i32 + 1, j as i32 - 1, future_grid, mov_dt, grass_element());
-                        active = true;
-                    }
-                    if left.element == Element::SolidDirt && up_left.element == Element::Air {
-                        set(i as i32 - 1, j as i32 - 1, future_grid, mov_dt, grass_element());
-                        active = true;
+                }
+            },
+            // INFO: For grass and the other plants
+            Action::Grow(growth) => plants::grow(future_grid, i, j, mov_dt, growth),
+            // INFO: For acid, dissolves one of the solids or powders around it, the lifetime is how many cells it can still dissolve
+            Action::Corrode => {
+                let mut targets = vec![];
+                for (x, y) in [(i as i32, j as i32 - 1), (i as i32, j as i32 + 1), (i as i32 - 1, j as i32), (i as i32 + 1, j as i32)] {
+                    let other = get(x, y, future_grid, mov_dt);
+                    let resistance = registry().get(other.element).acid_resistance;
+                    if matches!(other.state, State::Solid | State::Powder) && resistance < 1. {
+                        targets.push((x, y, other, resistance));
                     }
+                }
+                if targets.is_empty() {
+                    break 'action;
+                }
 
-                    let mut height = 1;
-                    let growth_chance = fastrand::f32();
-                    while growth_chance < 1 as f32 / (height as f32).powf(2.) {
-                        if get(i as i32, j as i32 - height,  future_grid, mov_dt).element != Element::Air || height >= 6 {
-                            break;
-                        }
-                        let mut grass_el = grass_element();
-                        grass_el.action = None;
-                        set(i as i32, j as i32 - height, future_grid, mov_dt, grass_el.clone());
-                        height += 1;
-                    }
+                *mov_dt.keep_active = true;
+                mov_dt.dirty_rect.set_temp(i, j);
 
-                    if !active {
-                        future_grid[i][j].action = None;
-                    }
+                let (x, y, other, resistance) = targets[mov_dt.rng.usize(..targets.len())];
+                if mov_dt.rng.f32() >= (1. - resistance) * CORRODE_RATE {
+                    break 'action;
+                }
+
+                // INFO: Dissolving fumes, half of the eaten cells turn into smoke
+                let dissolved = if mov_dt.rng.bool() {
+                    add_pressure(mov_dt.pressure, i, j, GAS_PRESSURE);
+                    smoke_element()
+                } else {
+                    air_element()
+                };
+                if other.collider_type != dissolved.collider_type {
+                    set_colliders_dirty(x, y, mov_dt);
+                }
+                set(x, y, future_grid, mov_dt, dissolved);
 
-                    *mov_dt.keep_active = true;
-                    mov_dt.dirty_rect.set_temp(i, j);
+                future_grid[i][j].lifetime -= 1;
+                if future_grid[i][j].lifetime <= 0 {
+                    set(i as i32, j as i32, future_grid, mov_dt, air_element());
                 }
             }
-        },
-        _ => ()
+            // INFO: For batteries, keeps sparking the conductors around them
+            Action::Power => electricity::power(future_grid, i, j, mov_dt),
+            Action::Sense(element) => logic::sense(future_grid, i, j, mov_dt, element),
+            Action::Gate(kind) => logic::gate(future_grid, i, j, mov_dt, kind),
+            Action::Door(panel) => logic::door(future_grid, i, j, mov_dt, panel),
+            Action::Piston(arm) => logic::piston(future_grid, i, j, mov_dt, arm),
+            Action::Creature(creature) => creatures::update(future_grid, i, j, mov_dt, creature),
+        }
     }
 }
 
-pub fn is_flammable(cell: &Cell) -> bool {
-    matches!(cell.element, Element::Wood | Element::SawDust | Element::Coal
-                            | Element::Methane | Element::Water  | Element::Petrol | Element::Grass
-                            | Element::Snow | Element::Ice)
+// INFO: True if two of the present elements (indexed by element id) react with each other
+pub fn can_react(present: &[bool]) -> bool {
+    let registry = registry();
+    registry
+        .elements()
+        .filter(|element| present[element.0 as usize])
+        .any(|element| registry.get(element).reactions.iter().any(|reaction| present[reaction.with.0 as usize]))
 }
 
-// INFO: Lifetime -1 burns up immediately, -100 burns forever
-pub fn get_flammable_info(element: &Element) -> (i32, Cell, bool, bool, bool) {
-    match element {
-        Element::Wood => (300, air_element(), true, true, false),
-        Element::Coal => (400, smoke_element(), true, true, false),
-        Element::SawDust => (215, air_element(), true, true, false),
-        Element::Methane => (0, fire_element(), true, false, false),
-        Element::Water => (-1, steam_element(), false, false, false),
-        Element::Petrol => (80, fire_element(), true, false, false),
-        Element::Lava => (-100, fire_element(), true, false, true),
-        Element::Grass => (2, fire_element(), true, true, false),
-        Element::Snow => (-1, water_element(), false, false, false),
-        Element::Ice => (-1, water_element(), false, false, false),
-        _ => (0, air_element(), false, false, false)
+// INFO: Reactions of the cell with its four neighbors, returns true if the cell itself turned into something else
+pub fn handle_reactions(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
+    let reactions = &registry().get(future_grid[i][j].element).reactions;
+    if reactions.is_empty() {
+        return false;
     }
+
+    for (x, y) in [(i as i32, j as i32 - 1), (i as i32, j as i32 + 1), (i as i32 - 1, j as i32), (i as i32 + 1, j as i32)] {
+        let with = get_element(x, y, future_grid, mov_dt);
+        if !reactions.iter().any(|reaction| reaction.with == with) {
+            continue;
+        }
+        let other = get(x, y, future_grid, mov_dt);
+        for reaction in reactions.iter().filter(|reaction| reaction.with == other.element) {
+            if reaction.chance < 1. && mov_dt.rng.f32() >= reaction.chance {
+                continue;
+            }
+
+            if let Some(with_into) = reaction.with_into {
+                let cell = reaction_cell(with_into, reaction);
+                if cell.collider_type != other.collider_type {
+                    set_colliders_dirty(x, y, mov_dt);
+                }
+                set(x, y, future_grid, mov_dt, cell);
+            }
+            if react(future_grid, i, j, mov_dt, reaction) {
+                return true;
+            }
+            // INFO: The neighbor is something else now, the other reactions with it do not apply anymore
+            if reaction.with_into.is_some() {
+                break;
+            }
+        }
+    }
+    false
+}
+
+/*
+    INFO: A cell at its ignition temperature burns in place until its reaction with Fire happens to it, the chance of the reaction
+    is what makes it burn long or short. While it burns it stays as hot as the reaction's heat and sets the air around it on fire
+*/
+pub fn burn(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
+    let reactions = &registry().get(future_grid[i][j].element).reactions;
+    let Some(reaction) = reactions.iter().find(|reaction| reaction.with == Element::Fire) else {
+        return false;
+    };
+    if reaction.chance >= 1. || mov_dt.rng.f32() < reaction.chance {
+        return react(future_grid, i, j, mov_dt, reaction);
+    }
+
+    if let Some(heat) = reaction.heat {
+        future_grid[i][j].temperature = future_grid[i][j].temperature.max(heat);
+    }
+    if mov_dt.rng.f32() < FLAME_CHANCE {
+        let (x, y) = [(i as i32, j as i32 - 1), (i as i32, j as i32 + 1), (i as i32 - 1, j as i32), (i as i32 + 1, j as i32)][mov_dt.rng.usize(..4)];
+        if get(x, y, future_grid, mov_dt).element == Element::Air {
+            set(x, y, future_grid, mov_dt, fire_element());
+        }
+    }
+    false
+}
+
+// INFO: Turns the cell into what the reaction makes of it, explosives detonate instead. Returns true if the cell changed
+fn react(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, reaction: &Reaction) -> bool {
+    let Some(into) = reaction.into else {
+        return false;
+    };
+    if let Some(explosive) = registry().get(future_grid[i][j].element).explosive {
+        detonate(future_grid, i, j, mov_dt, explosive);
+        return true;
+    }
+
+    let cell = reaction_cell(into, reaction);
+    if cell.collider_type != future_grid[i][j].collider_type {
+        *mov_dt.colliders_dirty = true;
+    }
+    if cell.state == State::Gas && future_grid[i][j].state != State::Gas {
+        add_pressure(mov_dt.pressure, i, j, GAS_PRESSURE);
+    }
+    set(i as i32, j as i32, future_grid, mov_dt, cell);
+    true
+}
+
+fn reaction_cell(element: Element, reaction: &Reaction) -> Cell {
+    let mut cell = el_from_enum(element);
+    if let Some(heat) = reaction.heat {
+        cell.temperature = cell.temperature.max(heat);
+    }
+    cell
 }
 
 pub fn set_action(i: i32, j: i32, f_grid: &mut Grid, mov_dt: &mut MovData, action: Option<Action>) {
 	if in_bound(i, j) {
 		f_grid[i as usize][j as usize].action = action;
+
+		*mov_dt.keep_active = true;
+		mov_dt.dirty_rect.set_temp(i as usize, j as usize);
 	} else {
 		let wanted_chunk = get_wanted_chunk(mov_dt.index, i, j);
 		
-		match mov_dt.chunks.get_mut(&wanted_chunk) {
-		    Some(chunk) => {
-		        let (x, y) = get_new_element_coord(i, j);
-		        chunk.grid[x as usize][y as usize].action = action;
-		    },
-		    _ => ()
+		if let Some(chunk) = mov_dt.chunks.get_mut(&wanted_chunk) {
+		    let (x, y) = get_new_element_coord(i, j);
+		    chunk.grid[x as usize][y as usize].action = action;
+
+			mov_dt.wake_ups.push((wanted_chunk, (x as usize, y as usize)));
 		}
 	}
 }
@@ -0,0 +1,560 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use serde::Deserialize;
+
+use super::{
+    chunk::ROWS,
+    element::{Action, Creature, CreatureKind, ElColliderType, Element, Growth, LogicGate, State, BUILTIN_ELEMENTS},
+    heat::AMBIENT,
+};
+
+const DEFAULT_DEFINITIONS: &str = include_str!("../assets/elements.ron");
+
+static REGISTRY: OnceLock<ElementRegistry> = OnceLock::new();
+
+// INFO: Blasts may only reach into the neighboring chunks and not as far as a blast of a chunk updated in the same pass
+const MAX_BLAST_RADIUS: i32 = ROWS as i32 / 4;
+
+// INFO: How the cells of an element move, every behavior is a movement function in el_movement
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+pub enum Movement {
+    #[default]
+    Static,
+    Powder,
+    Liquid,
+    Gas,
+    Fire,
+    FireworkShell,
+    FireworkEmber,
+}
+
+// INFO: Explosives detonate when they burn, everything in the radius gets hit with the force falling off towards the edge
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Explosive {
+    pub radius: i32,
+    pub force: f32,
+}
+
+// INFO: A reaction of an element with one of its four neighbors, the element that owns it checks for it every frame. Burning is a reaction with Fire
+#[derive(Clone, Debug)]
+pub struct Reaction {
+    pub with: Element,
+    pub chance: f32,
+    pub into: Option<Element>,
+    pub with_into: Option<Element>,
+    // INFO: The cells the reaction turns into are at least this hot, for the reaction with Fire it is also the heat the element burns with
+    pub heat: Option<f32>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EditorButton {
+    pub label: String,
+    pub color: (u8, u8, u8),
+    // INFO: Lifetime the placed cells get instead of the element's default one
+    #[serde(default)]
+    pub lifetime: Option<i32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ElementDef {
+    pub name: String,
+    pub state: State,
+    pub density: f32,
+    pub drag: f32,
+    pub color: [u8; 4],
+    pub lifetime: i32,
+    pub texture: Option<String>,
+    pub collider_type: ElColliderType,
+    pub movement: Movement,
+    pub action: Option<Action>,
+    pub reactions: Vec<Reaction>,
+    // INFO: At this temperature the element burns as if a flame touched it, it reacts the way its reaction with Fire says
+    pub ignition: Option<f32>,
+    pub temperature: f32,
+    pub heat_capacity: f32,
+    pub conductivity: f32,
+    // INFO: The cell never gets colder than this while it exists
+    pub heat_source: Option<f32>,
+    // INFO: Phase changes, the element turns into another one at or above (heated) or at or below (cooled) a temperature
+    pub heated: Option<(f32, Element)>,
+    pub cooled: Option<(f32, Element)>,
+    // INFO: 1 is immune to acid, 0 dissolves the fastest
+    pub acid_resistance: f32,
+    pub explosive: Option<Explosive>,
+    // INFO: Solid cells hit by a blast stronger than their hardness get crushed into another element, or destroyed if they have none
+    pub hardness: f32,
+    pub crushed_into: Option<Element>,
+    // INFO: Sparks run along connected cells of conducting elements
+    pub conducts_electricity: bool,
+    // INFO: Creatures dig through diggable elements and eat edible ones
+    pub diggable: bool,
+    pub edible: bool,
+    // INFO: How strongly the liquid erodes and carries sediment while it flows, and the chance to be worn down by it into another element
+    pub erosion: f32,
+    pub eroded: Option<(f32, Element)>,
+    // INFO: For liquids, the chance to skip moving each frame, how fast they speed up sideways and the speed they stop speeding up at
+    pub viscosity: f32,
+    pub dispersion: f32,
+    pub max_dispersion: f32,
+    // INFO: For gases, the frames in which half of them disappear and the frames under a ceiling before they condense into another element
+    pub half_life: Option<f32>,
+    pub condenses: Option<(i32, Element)>,
+    // INFO: Burning cells need a neighbor with oxygen
+    pub oxygen: bool,
+    /*
+        INFO: For powders, the water cells they hold when placed and how many they soak up,
+        the wetness they turn into another element at (sand into wet sand, dirt into mud) and the wetness they dry back into another element at
+    */
+    pub wetness: u8,
+    pub absorbs: u8,
+    pub soaked: Option<(u8, Element)>,
+    pub dried: Option<(u8, Element)>,
+    // INFO: For powders, the chance to stop sliding each frame once they landed and the chance to stay put when a neighbor falls
+    pub friction: f32,
+    pub inertia: f32,
+    // INFO: Structural cells make up structures that hold as long as they are connected to an anchor, see support.rs. Unsupported cells fall apart into another element
+    pub structural: bool,
+    pub anchor: bool,
+    pub collapses_into: Option<Element>,
+    pub editor: Option<EditorButton>,
+}
+
+pub struct ElementRegistry {
+    defs: Vec<ElementDef>,
+    by_name: HashMap<String, Element>,
+    editor_elements: Vec<Element>,
+    // INFO: Directory of the definitions file, textures that are not built in are loaded from here
+    pub dir: Option<PathBuf>,
+}
+
+/*
+    INFO: The definitions as they are written in the file,
+    elements are referred to by name and only become ids once every element is known
+*/
+#[derive(Deserialize)]
+struct DefinitionsFile {
+    elements: Vec<ElementFile>,
+    #[serde(default)]
+    reactions: Vec<ReactionFile>,
+}
+
+#[derive(Deserialize)]
+struct ElementFile {
+    name: String,
+    state: State,
+    density: f32,
+    drag: f32,
+    color: (u8, u8, u8, u8),
+    #[serde(default = "default_lifetime")]
+    lifetime: i32,
+    #[serde(default)]
+    texture: Option<String>,
+    #[serde(default = "default_collider_type")]
+    collider_type: ElColliderType,
+    #[serde(default)]
+    movement: Movement,
+    #[serde(default)]
+    action: Option<ActionFile>,
+    #[serde(default)]
+    ignition: Option<f32>,
+    #[serde(default = "default_temperature")]
+    temperature: f32,
+    #[serde(default = "default_heat_capacity")]
+    heat_capacity: f32,
+    #[serde(default = "default_conductivity")]
+    conductivity: f32,
+    #[serde(default)]
+    heat_source: Option<f32>,
+    #[serde(default)]
+    heated: Option<(f32, String)>,
+    #[serde(default)]
+    cooled: Option<(f32, String)>,
+    #[serde(default = "default_acid_resistance")]
+    acid_resistance: f32,
+    #[serde(default)]
+    explosive: Option<Explosive>,
+    #[serde(default = "default_hardness")]
+    hardness: f32,
+    #[serde(default)]
+    crushed_into: Option<String>,
+    #[serde(default)]
+    conducts_electricity: bool,
+    #[serde(default)]
+    diggable: bool,
+    #[serde(default)]
+    edible: bool,
+    #[serde(default)]
+    erosion: f32,
+    #[serde(default)]
+    eroded: Option<(f32, String)>,
+    #[serde(default)]
+    viscosity: f32,
+    #[serde(default = "default_dispersion")]
+    dispersion: f32,
+    #[serde(default = "default_max_dispersion")]
+    max_dispersion: f32,
+    #[serde(default)]
+    half_life: Option<f32>,
+    #[serde(default)]
+    condenses: Option<(i32, String)>,
+    #[serde(default)]
+    oxygen: bool,
+    #[serde(default)]
+    wetness: u8,
+    #[serde(default)]
+    absorbs: u8,
+    #[serde(default)]
+    soaked: Option<(u8, String)>,
+    #[serde(default)]
+    dried: Option<(u8, String)>,
+    #[serde(default)]
+    friction: f32,
+    #[serde(default)]
+    inertia: f32,
+    #[serde(default)]
+    structural: bool,
+    #[serde(default)]
+    anchor: bool,
+    #[serde(default)]
+    collapses_into: Option<String>,
+    #[serde(default)]
+    editor: Option<EditorButton>,
+}
+
+#[derive(Deserialize)]
+enum ActionFile {
+    EmitSource(String),
+    Grow(Growth),
+    Corrode,
+    Power,
+    Sense(String),
+    Gate(LogicGate),
+    Door(String),
+    Piston(String),
+    Creature(CreatureKind),
+}
+
+#[derive(Deserialize)]
+struct ReactionFile {
+    a: String,
+    b: String,
+    #[serde(default)]
+    a_into: Option<String>,
+    #[serde(default)]
+    b_into: Option<String>,
+    #[serde(default = "default_chance")]
+    chance: f32,
+    #[serde(default)]
+    heat: Option<f32>,
+}
+
+fn default_chance() -> f32 {
+    1.
+}
+
+fn default_temperature() -> f32 {
+    AMBIENT
+}
+
+fn default_heat_capacity() -> f32 {
+    1.
+}
+
+fn default_conductivity() -> f32 {
+    0.05
+}
+
+fn default_acid_resistance() -> f32 {
+    0.5
+}
+
+fn default_hardness() -> f32 {
+    1.
+}
+
+fn default_dispersion() -> f32 {
+    3.
+}
+
+fn default_max_dispersion() -> f32 {
+    7.
+}
+
+fn default_lifetime() -> i32 {
+    -1
+}
+
+fn default_collider_type() -> ElColliderType {
+    ElColliderType::None
+}
+
+impl ElementRegistry {
+    /*
+        INFO: The built-in elements always get the same ids (the Element constants) no matter where they are in the file,
+        every other element gets the next free id in the order it is listed
+    */
+    pub fn from_ron(text: &str, dir: Option<&Path>) -> Result<Self, String> {
+        let definitions: DefinitionsFile = ron::from_str(text).map_err(|e| format!("Invalid element definitions: {}", e))?;
+        let files = &definitions.elements;
+
+        let mut order: Vec<Option<usize>> = vec![None; BUILTIN_ELEMENTS.len()];
+        for (file_index, file) in files.iter().enumerate() {
+            match BUILTIN_ELEMENTS.iter().position(|name| *name == file.name) {
+                Some(id) if order[id].is_some() => return Err(format!("The element '{}' is defined more than once", file.name)),
+                Some(id) => order[id] = Some(file_index),
+                None => order.push(Some(file_index)),
+            }
+        }
+
+        let mut by_name = HashMap::new();
+        for (id, file_index) in order.iter().enumerate() {
+            let file_index = file_index.ok_or_else(|| format!("The built-in element '{}' is not defined", BUILTIN_ELEMENTS[id]))?;
+            if by_name.insert(files[file_index].name.clone(), Element(id as u16)).is_some() {
+                return Err(format!("The element '{}' is defined more than once", files[file_index].name));
+            }
+        }
+
+        let lookup = |name: &str| by_name.get(name).copied().ok_or_else(|| format!("Unknown element '{}'", name));
+
+        let mut defs = vec![];
+        for file_index in order.iter().flatten() {
+            let file = &files[*file_index];
+            defs.push(ElementDef {
+                name: file.name.clone(),
+                state: file.state,
+                density: file.density,
+                drag: file.drag,
+                color: [file.color.0, file.color.1, file.color.2, file.color.3],
+                lifetime: file.lifetime,
+                texture: file.texture.clone(),
+                collider_type: file.collider_type,
+                movement: file.movement,
+                action: match &file.action {
+                    None => None,
+                    Some(ActionFile::EmitSource(name)) => Some(Action::EmitSource(lookup(name)?)),
+                    Some(ActionFile::Grow(growth)) => Some(Action::Grow(*growth)),
+                    Some(ActionFile::Corrode) => Some(Action::Corrode),
+                    Some(ActionFile::Power) => Some(Action::Power),
+                    Some(ActionFile::Sense(name)) => Some(Action::Sense(lookup(name)?)),
+                    Some(ActionFile::Gate(kind)) => Some(Action::Gate(*kind)),
+                    Some(ActionFile::Door(name)) => Some(Action::Door(lookup(name)?)),
+                    Some(ActionFile::Piston(name)) => Some(Action::Piston(lookup(name)?)),
+                    Some(ActionFile::Creature(kind)) => Some(Action::Creature(Creature::new(*kind))),
+                },
+                reactions: vec![],
+                ignition: file.ignition,
+                temperature: file.temperature,
+                heat_capacity: file.heat_capacity,
+                conductivity: file.conductivity,
+                heat_source: file.heat_source,
+                heated: match &file.heated {
+                    Some((temperature, name)) => Some((*temperature, lookup(name)?)),
+                    None => None,
+                },
+                cooled: match &file.cooled {
+                    Some((temperature, name)) => Some((*temperature, lookup(name)?)),
+                    None => None,
+                },
+                acid_resistance: file.acid_resistance,
+                explosive: match file.explosive {
+                    Some(explosive) if explosive.radius > MAX_BLAST_RADIUS => {
+                        return Err(format!("The blast radius of '{}' is larger than {}", file.name, MAX_BLAST_RADIUS))
+                    }
+                    explosive => explosive,
+                },
+                hardness: file.hardness,
+                crushed_into: match &file.crushed_into {
+                    Some(name) => Some(lookup(name)?),
+                    None => None,
+                },
+                conducts_electricity: file.conducts_electricity,
+                diggable: file.diggable,
+                edible: file.edible,
+                erosion: file.erosion,
+                eroded: match &file.eroded {
+                    Some((chance, name)) => Some((*chance, lookup(name)?)),
+                    None => None,
+                },
+                viscosity: match file.viscosity {
+                    viscosity if !(0.0..1.0).contains(&viscosity) => {
+                        return Err(format!("The viscosity of '{}' has to be at least 0 and below 1", file.name))
+                    }
+                    viscosity => viscosity,
+                },
+                dispersion: file.dispersion,
+                max_dispersion: file.max_dispersion,
+                half_life: match file.half_life {
+                    Some(half_life) if half_life <= 0. => {
+                        return Err(format!("The half life of '{}' has to be above 0", file.name))
+                    }
+                    half_life => half_life,
+                },
+                condenses: match &file.condenses {
+                    Some((frames, name)) => Some((*frames, lookup(name)?)),
+                    None => None,
+                },
+                oxygen: file.oxygen,
+                wetness: match file.wetness {
+                    wetness if wetness > file.absorbs => {
+                        return Err(format!("'{}' can not hold more water than it absorbs", file.name))
+                    }
+                    wetness => wetness,
+                },
+                absorbs: file.absorbs,
+                soaked: match &file.soaked {
+                    Some((wetness, _)) if *wetness > file.absorbs => {
+                        return Err(format!("'{}' can not get soaked at a wetness above what it absorbs", file.name))
+                    }
+                    Some((wetness, name)) => Some((*wetness, lookup(name)?)),
+                    None => None,
+                },
+                dried: match &file.dried {
+                    Some((wetness, name)) => Some((*wetness, lookup(name)?)),
+                    None => None,
+                },
+                friction: match file.friction {
+                    friction if !(0.0..=1.0).contains(&friction) => {
+                        return Err(format!("The friction of '{}' has to be between 0 and 1", file.name))
+                    }
+                    friction => friction,
+                },
+                inertia: match file.inertia {
+                    inertia if !(0.0..=1.0).contains(&inertia) => {
+                        return Err(format!("The inertia of '{}' has to be between 0 and 1", file.name))
+                    }
+                    inertia => inertia,
+                },
+                structural: file.structural,
+                anchor: match file.anchor {
+                    true if !file.structural => return Err(format!("The anchor '{}' has to be structural", file.name)),
+                    anchor => anchor,
+                },
+                collapses_into: match &file.collapses_into {
+                    Some(name) => Some(lookup(name)?),
+                    None => None,
+                },
+                editor: file.editor.clone(),
+            });
+        }
+
+        for reaction in &definitions.reactions {
+            let a = lookup(&reaction.a)?;
+            let with_into = match &reaction.b_into {
+                Some(name) => Some(lookup(name)?),
+                None => None,
+            };
+            let into = match &reaction.a_into {
+                Some(name) => Some(lookup(name)?),
+                None => None,
+            };
+            defs[a.0 as usize].reactions.push(Reaction { with: lookup(&reaction.b)?, chance: reaction.chance, into, with_into, heat: reaction.heat });
+        }
+
+        // INFO: Catching fire means reacting with Fire, explosives go off once they catch fire
+        for def in &defs {
+            let burns = def.reactions.iter().any(|reaction| reaction.with == Element::Fire && reaction.into.is_some());
+            if def.ignition.is_some() && !burns {
+                return Err(format!("'{}' has an ignition temperature but does not burn with Fire", def.name));
+            }
+            if def.explosive.is_some() && def.ignition.is_none() {
+                return Err(format!("The explosive '{}' needs an ignition temperature", def.name));
+            }
+        }
+
+        let editor_elements = files.iter().filter(|file| file.editor.is_some()).map(|file| by_name[&file.name]).collect();
+
+        Ok(Self {
+            defs,
+            by_name,
+            editor_elements,
+            dir: dir.map(Path::to_path_buf),
+        })
+    }
+
+    pub fn get(&self, element: Element) -> &ElementDef {
+        &self.defs[element.0 as usize]
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<Element> {
+        self.by_name.get(name).copied()
+    }
+
+    pub fn elements(&self) -> impl ExactSizeIterator<Item = Element> {
+        (0..self.defs.len() as u16).map(Element)
+    }
+
+    // INFO: The elements that have an editor button, in the order of the definitions file
+    pub fn editor_elements(&self) -> &[Element] {
+        &self.editor_elements
+    }
+}
+
+// INFO: The registry every cell looks its element up in, the built-in definitions are used unless a file was loaded first
+pub fn registry() -> &'static ElementRegistry {
+    REGISTRY.get_or_init(|| ElementRegistry::from_ron(DEFAULT_DEFINITIONS, None).expect("The built-in element definitions are invalid"))
+}
+
+// INFO: Has to be called before anything touches the registry (creating a ChunkManager does)
+pub fn load_registry(path: &Path) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let registry = ElementRegistry::from_ron(&text, path.parent())?;
+    REGISTRY.set(registry).map_err(|_| "The element registry was already loaded".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ElementRegistry, DEFAULT_DEFINITIONS};
+
+    // INFO: The error of the built-in definitions with one piece of them replaced
+    fn error_with(from: &str, to: &str) -> String {
+        assert!(DEFAULT_DEFINITIONS.contains(from), "The definitions do not contain {}", from);
+        match ElementRegistry::from_ron(&DEFAULT_DEFINITIONS.replacen(from, to, 1), None) {
+            Ok(_) => panic!("{} was accepted", to),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn built_in_definitions_are_valid() {
+        ElementRegistry::from_ron(DEFAULT_DEFINITIONS, None).unwrap();
+    }
+
+    #[test]
+    fn unknown_element_names_are_rejected() {
+        assert_eq!(error_with("(a: \"Water\", b: \"Lava\"", "(a: \"Water\", b: \"Magma\""), "Unknown element 'Magma'");
+        assert_eq!(error_with("crushed_into: Some(\"Dirt\")", "crushed_into: Some(\"Rubble\")"), "Unknown element 'Rubble'");
+        assert_eq!(error_with("condenses: Some((300, \"Water\"))", "condenses: Some((300, \"Rain\"))"), "Unknown element 'Rain'");
+    }
+
+    #[test]
+    fn viscosity_has_to_be_below_one() {
+        let error = "The viscosity of 'Lava' has to be at least 0 and below 1";
+        assert_eq!(error_with("viscosity: 0.8", "viscosity: 1.0"), error);
+        assert_eq!(error_with("viscosity: 0.8", "viscosity: -0.1"), error);
+    }
+
+    #[test]
+    fn blast_radius_has_to_stay_in_the_neighboring_chunks() {
+        assert_eq!(
+            error_with("explosive: Some((radius: 30, force: 6.0))", "explosive: Some((radius: 37, force: 6.0))"),
+            "The blast radius of 'C4' is larger than 36"
+        );
+    }
+
+    #[test]
+    fn burning_needs_a_reaction_with_fire_and_explosives_an_ignition_temperature() {
+        assert_eq!(
+            error_with("        (a: \"Wood\", b: \"Fire\", a_into: Some(\"Smoke\"), chance: 0.015, heat: Some(600.0)),\n", ""),
+            "'Wood' has an ignition temperature but does not burn with Fire"
+        );
+        assert_eq!(
+            error_with("            ignition: Some(300.0),\n            explosive: Some((radius: 16", "            explosive: Some((radius: 16"),
+            "The explosive 'TNT' needs an ignition temperature"
+        );
+    }
+}
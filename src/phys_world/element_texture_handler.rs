@@ -1,30 +1,56 @@
 use std::collections::HashMap;
 
-use super::element::Element;
+use super::{element::Element, element_registry::registry};
 
 pub const EL_TEX_WIDTH: usize = 16;
 pub const EL_TEX_HEIGHT: usize = 16;
 type TextureData = [[[u8; 4]; EL_TEX_WIDTH]; EL_TEX_HEIGHT];
 
+const BUILTIN_TEXTURES: [(&str, &[u8]); 8] = [
+	("wall.png", include_bytes!("../assets/element_textures/wall.png")),
+	("rock.png", include_bytes!("../assets/element_textures/rock.png")),
+	("dirt.png", include_bytes!("../assets/element_textures/dirt.png")),
+	("wood.png", include_bytes!("../assets/element_textures/wood.png")),
+	("sand.png", include_bytes!("../assets/element_textures/sand.png")),
+	("brick.png", include_bytes!("../assets/element_textures/brick.png")),
+	("snow.png", include_bytes!("../assets/element_textures/snow.png")),
+	("ice.png", include_bytes!("../assets/element_textures/ice.png")),
+];
+
 pub struct ElementTexHandler {
 	textures: HashMap<Element, TextureData>
 }
 
+impl Default for ElementTexHandler {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl ElementTexHandler {
 	pub fn new() -> Self {
 		let mut textures = HashMap::new();
 
-		textures.insert(Element::Solid, get_tex_data(include_bytes!("../assets/element_textures/wall.png")));
-		textures.insert(Element::Gravel, get_tex_data(include_bytes!("../assets/element_textures/rock.png")));
-		textures.insert(Element::Dirt, get_tex_data(include_bytes!("../assets/element_textures/dirt.png")));
-		textures.insert(Element::SolidDirt, get_tex_data(include_bytes!("../assets/element_textures/dirt.png")));
-		textures.insert(Element::Wood, get_tex_data(include_bytes!("../assets/element_textures/wood.png")));
-		textures.insert(Element::Sand, get_tex_data(include_bytes!("../assets/element_textures/sand.png")));
-		textures.insert(Element::Brick, get_tex_data(include_bytes!("../assets/element_textures/brick.png")));
-		textures.insert(Element::Snow, get_tex_data(include_bytes!("../assets/element_textures/snow.png")));
-		textures.insert(Element::Ice, get_tex_data(include_bytes!("../assets/element_textures/ice.png")));
-		
-		
+		for element in registry().elements() {
+			let Some(name) = &registry().get(element).texture else {
+				continue;
+			};
+
+			// INFO: Textures that ship with the game are built in, others are read from next to the definitions file
+			if let Some((_, data)) = BUILTIN_TEXTURES.iter().find(|(builtin, _)| builtin == name) {
+				textures.insert(element, get_tex_data(data));
+				continue;
+			}
+
+			let path = registry().dir.clone().unwrap_or_default().join(name);
+			match std::fs::read(&path) {
+				Ok(data) if image::load_from_memory(&data).is_ok() => {
+					textures.insert(element, get_tex_data(&data));
+				},
+				_ => eprintln!("Could not load the texture {} of {:?}", path.display(), element)
+			}
+		}
+
 		Self {
 			textures
 		}
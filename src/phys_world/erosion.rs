@@ -0,0 +1,57 @@
+use super::{
+    base_movement::{get, set, set_colliders_dirty},
+    chunk::{Grid, MovData},
+    element::{el_from_enum, ElColliderType, State},
+    element_registry::{registry, Movement},
+};
+
+// INFO: Liquids flowing slower than this neither erode nor carry anything, flows from FULL_FLOW on erode the most
+const MIN_FLOW: f32 = 2.;
+const FULL_FLOW: f32 = 8.;
+// INFO: Chance per frame that a liquid with an erosion of 1 flowing at full speed picks up a powder it touches
+const CARRY_CHANCE: f32 = 0.2;
+// INFO: How much of the speed of the flow picked up sediment gets
+const CARRY: f32 = 0.8;
+
+/*
+    INFO: Flowing liquids with an erosion wear down the terrain they run over and pick up the powders they touch.
+    Every frame a flowing cell looks at the cell below it or the one it flows into. Worn down cells turn into their
+    `eroded` element (solid dirt into dirt), picked up powders get pushed along with the flow as sediment
+    until they sink back down and settle where it slows. The faster the flow, the more it erodes,
+    the heavier the powder compared to the liquid, the less it gets carried
+*/
+pub fn erode(f_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
+    let liquid = f_grid[i][j];
+    let erosion = registry().get(liquid.element).erosion;
+    let flow = liquid.velocity.length();
+    if erosion <= 0. || flow < MIN_FLOW {
+        return;
+    }
+    let strength = erosion * (flow / FULL_FLOW).min(1.);
+
+    let (x, y) = if liquid.velocity.x == 0. || mov_dt.rng.bool() {
+        (i as i32, j as i32 + 1)
+    } else {
+        (i as i32 + liquid.velocity.x.signum() as i32, j as i32)
+    };
+    let mut cell = get(x, y, f_grid, mov_dt);
+    let def = registry().get(cell.element);
+
+    if let Some((chance, into)) = def.eroded {
+        if mov_dt.rng.f32() < chance * strength {
+            if cell.collider_type == ElColliderType::Chunk {
+                set_colliders_dirty(x, y, mov_dt);
+            }
+            set(x, y, f_grid, mov_dt, el_from_enum(into));
+        }
+        return;
+    }
+
+    if cell.state == State::Powder && def.movement == Movement::Powder {
+        let weight = (liquid.density / cell.density).min(1.);
+        if mov_dt.rng.f32() < CARRY_CHANCE * strength * weight {
+            cell.velocity.x = liquid.velocity.x * CARRY;
+            set(x, y, f_grid, mov_dt, cell);
+        }
+    }
+}
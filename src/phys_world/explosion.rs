@@ -0,0 +1,92 @@
+use glam::Vec2;
+
+use super::{
+    base_movement::{get, set, set_colliders_dirty},
+    chunk::{Grid, MovData, COLS, ROWS},
+    element::{air_element, el_from_enum, fire_element, smoke_element, Cell, Element, State},
+    element_registry::{registry, Explosive},
+    pressure,
+};
+
+// INFO: Speed a loose cell gets thrown with by a blast of force 1 right next to it
+const BLAST_SPEED: f32 = 6.;
+// INFO: Chance that air in the inner half of a blast catches fire
+const FIREBALL_CHANCE: f32 = 0.3;
+
+// INFO: A detonation in world cells, kept on the chunk until the rigid bodies around it got pushed
+#[derive(Clone, Copy, Debug)]
+pub struct Explosion {
+    pub position: Vec2,
+    pub radius: f32,
+    pub force: f32,
+}
+
+/*
+    INFO: Blows up the explosive cell at i, j. Solids that are not hard enough get crushed or destroyed,
+    loose cells get thrown away from the center, other explosives in the radius get heated to their ignition temperature and go off next frame
+    and the air gets pushed through the pressure field
+*/
+pub fn detonate(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, explosive: Explosive) {
+    set(i as i32, j as i32, future_grid, mov_dt, fire_element());
+    pressure::explode_field(mov_dt.pressure, i as i32, j as i32, explosive.radius, explosive.force);
+    mov_dt.explosions.push(Explosion {
+        position: Vec2::new((mov_dt.index.0 * COLS as i32 + i as i32) as f32, (mov_dt.index.1 * ROWS as i32 + j as i32) as f32),
+        radius: explosive.radius as f32,
+        force: explosive.force,
+    });
+
+    let radius = explosive.radius;
+    for x in -radius..=radius {
+        for y in -radius..=radius {
+            let offset = Vec2::new(x as f32, y as f32);
+            let dist = offset.length();
+            if dist > radius as f32 || (x == 0 && y == 0) {
+                continue;
+            }
+
+            let power = explosive.force * (1. - dist / radius as f32);
+            let (cx, cy) = (i as i32 + x, j as i32 + y);
+            let cell = get(cx, cy, future_grid, mov_dt);
+            let def = registry().get(cell.element);
+
+            if let (Some(_), Some(ignition)) = (def.explosive, def.ignition) {
+                if cell.temperature < ignition {
+                    set(cx, cy, future_grid, mov_dt, Cell { temperature: ignition, ..cell });
+                }
+                continue;
+            }
+
+            let mut hit = match cell.state {
+                State::Solid if power <= def.hardness => continue,
+                State::Solid => match def.crushed_into {
+                    Some(crushed_into) => el_from_enum(crushed_into),
+                    None => debris(mov_dt),
+                },
+                State::Gas if cell.element == Element::Air => {
+                    if dist > radius as f32 / 2. || mov_dt.rng.f32() >= FIREBALL_CHANCE {
+                        continue;
+                    }
+                    fire_element()
+                }
+                _ => cell,
+            };
+
+            if hit.state != State::Solid && hit.state != State::Plasma {
+                hit.velocity += offset / dist * power * BLAST_SPEED;
+            }
+            if hit.collider_type != cell.collider_type {
+                set_colliders_dirty(cx, cy, mov_dt);
+            }
+            set(cx, cy, future_grid, mov_dt, hit);
+        }
+    }
+}
+
+// INFO: What a destroyed solid leaves behind
+fn debris(mov_dt: &mut MovData) -> Cell {
+    match mov_dt.rng.u8(0..10) {
+        0..=3 => fire_element(),
+        4..=5 => smoke_element(),
+        _ => air_element(),
+    }
+}
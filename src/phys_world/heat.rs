@@ -0,0 +1,113 @@
+use crate::phys_world::{
+    base_movement::{get_new_element_coord, get_wanted_chunk, set},
+    chunk::{in_bound, on_edge, Grid, MovData},
+    element::{el_from_enum, Element, State},
+    element_actions::{burn, has_oxygen},
+    element_registry::registry,
+    pressure::{add_pressure, GAS_PRESSURE},
+};
+
+// INFO: Temperature changes smaller than this do not keep a chunk awake, the heat around it is considered settled
+const SETTLED: f32 = 0.05;
+// INFO: Temperature of the elements that do not set their own one
+pub const AMBIENT: f32 = 20.;
+
+// INFO: Cells this close to the ambient temperature have no heat worth exchanging with each other
+pub fn at_ambient(temperature: f32) -> bool {
+    (temperature - AMBIENT).abs() < SETTLED / 2.
+}
+
+/*
+    INFO: Heat conduction with the four neighbors, then the phase changes and ignition of the cell.
+    Every cell exchanges heat with its neighbors and they do the same with it, so each exchange only moves half of the difference.
+    In a settled chunk (see chunk::scan_dirty_rect) the cells at the ambient temperature have nothing to exchange and skip it.
+    Returns true if the cell turned into another element
+*/
+pub fn handle_heat(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, settled: bool) -> bool {
+    let def = registry().get(future_grid[i][j].element);
+    if def.conductivity <= 0. && def.heat_source.is_none() && def.heated.is_none() && def.cooled.is_none() && def.ignition.is_none() {
+        return false;
+    }
+
+    if let Some(heat) = def.heat_source {
+        future_grid[i][j].temperature = future_grid[i][j].temperature.max(heat);
+    }
+
+    let skip_conduction = settled && !on_edge(i, j) && at_ambient(future_grid[i][j].temperature);
+    if def.conductivity > 0. && !skip_conduction {
+        for (x, y) in [(i as i32, j as i32 - 1), (i as i32, j as i32 + 1), (i as i32 - 1, j as i32), (i as i32 + 1, j as i32)] {
+            let (other, other_temperature) = get_heat(x, y, future_grid, mov_dt);
+            let other_def = registry().get(other);
+
+            let flow = def.conductivity.min(other_def.conductivity) * 0.5 * (future_grid[i][j].temperature - other_temperature);
+            if flow.abs() < SETTLED {
+                continue;
+            }
+
+            future_grid[i][j].temperature -= flow / def.heat_capacity;
+            add_temperature(x, y, future_grid, mov_dt, flow / other_def.heat_capacity);
+
+            *mov_dt.keep_active = true;
+            mov_dt.dirty_rect.set_temp(i, j);
+        }
+    }
+
+    let temperature = future_grid[i][j].temperature;
+    let phase_change = match (def.heated, def.cooled) {
+        (Some((threshold, into)), _) if temperature >= threshold => Some(into),
+        (_, Some((threshold, into))) if temperature <= threshold => Some(into),
+        _ => None,
+    };
+    if let Some(into) = phase_change {
+        let mut cell = el_from_enum(into);
+        cell.temperature = temperature;
+        if cell.collider_type != future_grid[i][j].collider_type {
+            *mov_dt.colliders_dirty = true;
+        }
+        if cell.state == State::Gas && future_grid[i][j].state != State::Gas {
+            add_pressure(mov_dt.pressure, i, j, GAS_PRESSURE);
+        }
+        set(i as i32, j as i32, future_grid, mov_dt, cell);
+        return true;
+    }
+
+    if let Some(ignition) = def.ignition {
+        if temperature >= ignition && (def.explosive.is_some() || has_oxygen(future_grid, i, j, mov_dt)) {
+            *mov_dt.keep_active = true;
+            mov_dt.dirty_rect.set_temp(i, j);
+            return burn(future_grid, i, j, mov_dt);
+        }
+    }
+
+    false
+}
+
+// INFO: Same as get, without copying the whole cell, this runs for every neighbor of every conducting cell
+fn get_heat(i: i32, j: i32, f_grid: &Grid, mov_dt: &MovData) -> (Element, f32) {
+    let cell = if in_bound(i, j) {
+        &f_grid[i as usize][j as usize]
+    } else {
+        let (x, y) = get_new_element_coord(i, j);
+        match mov_dt.chunks.get(&get_wanted_chunk(mov_dt.index, i, j)) {
+            Some(chunk) => &chunk.grid[x as usize][y as usize],
+            // INFO: Nothing to exchange heat with outside of the loaded world
+            None => return (Element::Air, 0.),
+        }
+    };
+    (cell.element, cell.temperature)
+}
+
+pub fn add_temperature(i: i32, j: i32, f_grid: &mut Grid, mov_dt: &mut MovData, amount: f32) {
+    if in_bound(i, j) {
+        f_grid[i as usize][j as usize].temperature += amount;
+        mov_dt.dirty_rect.set_temp(i as usize, j as usize);
+    } else {
+        let wanted_chunk = get_wanted_chunk(mov_dt.index, i, j);
+
+        if let Some(chunk) = mov_dt.chunks.get_mut(&wanted_chunk) {
+            let (x, y) = get_new_element_coord(i, j);
+            chunk.grid[x as usize][y as usize].temperature += amount;
+            mov_dt.wake_ups.push((wanted_chunk, (x as usize, y as usize)));
+        }
+    }
+}
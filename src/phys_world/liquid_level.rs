@@ -0,0 +1,191 @@
+use std::collections::{HashMap, VecDeque};
+
+use ahash::RandomState;
+use fastrand::Rng;
+use glam::Vec2;
+
+use super::{
+    chunk::{self, chunk_seed, Chunk, COLS, ROWS},
+    chunk_index::ChunkIndex,
+    chunk_manager::WorldChunks,
+    element::{Cell, Element, State},
+};
+
+// INFO: Leveling needs the whole body of a liquid, so it is only done every few frames
+pub const LEVEL_INTERVAL: u128 = 4;
+// INFO: Most cells one body moves from its highest surface to its lowest opening per leveling
+const MAX_MOVES: usize = 32;
+// INFO: An opening has to be this much lower than the surface, otherwise the cells would just swap back and forth
+const MIN_DROP: i32 = 2;
+// INFO: Surface cells that are still falling or getting thrown around are not moved
+const RESTING_SPEED: f32 = 1.;
+// INFO: Most liquid cells flood filled per leveling, the chunks that did not get their turn are leveled next time
+const LEVEL_BUDGET: usize = 65536;
+
+/*
+    INFO: Hydrostatic leveling of connected bodies of liquid.
+    liquid_movement only moves cells down and sideways, so the liquid in one arm of a U-tube would never rise in the other one.
+    Every body of a liquid (the connected cells of one element) that reaches into a chunk where liquid moved moves cells from its highest resting surface
+    to its lowest openings (gas cells next to the body that something holds up), which is what the pressure at the bottom of the body would do.
+    A body that is still not level moved cells, so its chunks are leveled again next time until it is.
+    Bodies bigger than the budget are only searched through partly, every part of a body is connected so the cells moved are still right
+*/
+pub fn level_liquids(chunks: &mut WorldChunks, chunk_index: &ChunkIndex, seed: u64, frame_count: u128) {
+    let mut visited: HashMap<(i32, i32), Vec<bool>, RandomState> = HashMap::default();
+    let mut budget = LEVEL_BUDGET;
+
+    // INFO: Starts at another chunk every time, so one big body can not keep the budget from the others
+    let mut moved: Vec<(i32, i32)> = chunk_index.iter().filter(|index| chunks[index].liquid_moved).collect();
+    if !moved.is_empty() {
+        let turn = (frame_count / LEVEL_INTERVAL) as usize % moved.len();
+        moved.rotate_left(turn);
+    }
+    for index in moved {
+        if budget == 0 {
+            return;
+        }
+        chunks.get_mut(&index).unwrap().liquid_moved = false;
+
+        for i in 0..COLS {
+            for j in 0..ROWS {
+                let cell = &chunks[&index].grid[i][j];
+                if cell.state != State::Liquid || is_visited(&visited, index, i, j) {
+                    continue;
+                }
+                let element = cell.element;
+
+                let start = (index.0 * COLS as i32 + i as i32, index.1 * ROWS as i32 + j as i32);
+                let mut rng = Rng::with_seed(chunk_seed(seed, start, frame_count));
+                let moves = find_moves(chunks, &mut visited, start, element, &mut rng, &mut budget);
+                for (from, to) in moves {
+                    swap_cells(chunks, from, to);
+                }
+
+                // INFO: The rest of the chunk gets its turn next time
+                if budget == 0 {
+                    chunks.get_mut(&index).unwrap().liquid_moved = true;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// INFO: Flood fills the body the start cell belongs to and pairs its highest surface cells with its lowest openings
+fn find_moves(
+    chunks: &WorldChunks,
+    visited: &mut HashMap<(i32, i32), Vec<bool>, RandomState>,
+    start: (i32, i32),
+    element: Element,
+    rng: &mut Rng,
+    budget: &mut usize,
+) -> Vec<((i32, i32), (i32, i32))> {
+    let mut surface = vec![];
+    let mut openings = vec![];
+
+    let mut queue = VecDeque::from([start]);
+    set_visited(visited, start);
+    while let Some((x, y)) = queue.pop_front() {
+        if *budget == 0 {
+            break;
+        }
+        *budget -= 1;
+
+        let resting = cell_at(chunks, x, y).is_some_and(|cell| cell.velocity.y.abs() < RESTING_SPEED);
+
+        for (nx, ny) in [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)] {
+            let Some(other) = cell_at(chunks, nx, ny) else {
+                continue;
+            };
+
+            if other.element == element {
+                if !is_visited_world(visited, nx, ny) {
+                    set_visited(visited, (nx, ny));
+                    queue.push_back((nx, ny));
+                }
+            } else if other.state == State::Gas {
+                if ny == y - 1 && resting {
+                    surface.push((x, y));
+                }
+                if is_supported(chunks, element, nx, ny) {
+                    openings.push((nx, ny));
+                }
+            }
+        }
+    }
+
+    // INFO: The same gas cell can be next to multiple cells of the body
+    openings.sort();
+    openings.dedup();
+
+    // INFO: Shuffled before sorting so cells on the same height are picked evenly instead of always from one side
+    rng.shuffle(&mut surface);
+    rng.shuffle(&mut openings);
+    surface.sort_by_key(|cell| cell.1);
+    openings.sort_by_key(|cell| -cell.1);
+
+    surface
+        .into_iter()
+        .zip(openings)
+        .take(MAX_MOVES)
+        .take_while(|(from, to)| to.1 - from.1 >= MIN_DROP)
+        .collect()
+}
+
+// INFO: A gas cell that liquid can be put into without it just falling out again
+fn is_supported(chunks: &WorldChunks, element: Element, x: i32, y: i32) -> bool {
+    match cell_at(chunks, x, y + 1) {
+        Some(below) => below.state != State::Gas && (below.state != State::Liquid || below.element == element),
+        None => true,
+    }
+}
+
+fn swap_cells(chunks: &mut WorldChunks, from: (i32, i32), to: (i32, i32)) {
+    let (Some(from_cell), Some(to_cell)) = (cell_at(chunks, from.0, from.1).copied(), cell_at(chunks, to.0, to.1).copied()) else {
+        return;
+    };
+
+    let mut liquid = from_cell;
+    liquid.velocity = Vec2::ZERO;
+    set_cell(chunks, to, liquid);
+    set_cell(chunks, from, to_cell);
+}
+
+fn set_cell(chunks: &mut WorldChunks, (x, y): (i32, i32), cell: Cell) {
+    let (index, i, j) = split(x, y);
+    if let Some(chunk) = chunks.get_mut(&index) {
+        chunk.grid[i][j] = cell;
+        chunk::update_byte(&mut chunk.bytes, i, j, &cell.color);
+        chunk.dirty_tex = true;
+        chunk.liquid_moved = true;
+        chunk::wake_up(chunk, i, j);
+    }
+}
+
+fn cell_at(chunks: &WorldChunks, x: i32, y: i32) -> Option<&Cell> {
+    let (index, i, j) = split(x, y);
+    chunks.get(&index).map(|chunk: &Chunk| &chunk.grid[i][j])
+}
+
+// INFO: The chunk a world cell is in and its position in the chunk
+fn split(x: i32, y: i32) -> ((i32, i32), usize, usize) {
+    (
+        (x.div_euclid(COLS as i32), y.div_euclid(ROWS as i32)),
+        x.rem_euclid(COLS as i32) as usize,
+        y.rem_euclid(ROWS as i32) as usize,
+    )
+}
+
+fn is_visited(visited: &HashMap<(i32, i32), Vec<bool>, RandomState>, index: (i32, i32), i: usize, j: usize) -> bool {
+    visited.get(&index).is_some_and(|cells| cells[i * ROWS + j])
+}
+
+fn is_visited_world(visited: &HashMap<(i32, i32), Vec<bool>, RandomState>, x: i32, y: i32) -> bool {
+    let (index, i, j) = split(x, y);
+    is_visited(visited, index, i, j)
+}
+
+fn set_visited(visited: &mut HashMap<(i32, i32), Vec<bool>, RandomState>, (x, y): (i32, i32)) {
+    let (index, i, j) = split(x, y);
+    visited.entry(index).or_insert_with(|| vec![false; COLS * ROWS])[i * ROWS + j] = true;
+}
@@ -0,0 +1,218 @@
+use super::{
+    base_movement::{get, set, set_colliders_dirty},
+    chunk::{Grid, MovData},
+    electricity::{spark, REFRACTORY},
+    element::{air_element, el_from_enum, Action, Cell, ElColliderType, Element, LogicGate, State},
+    element_registry::{registry, Movement},
+};
+
+// INFO: Frames a logic cell still counts as powered after its input stopped sparking, wires spark in pulses with a frame or two between them
+const HOLD: u8 = REFRACTORY;
+// INFO: Frames a delay gate holds a signal back, has to fit in the bits of the lifetime it is kept in
+const DELAY: u32 = 30;
+// INFO: Longest door a door motor opens and the furthest a piston extends
+const MAX_DOOR: i32 = 64;
+const MAX_EXTENSION: i32 = 32;
+// INFO: Most cells a piston pushes in front of its arm, it is blocked if there is no gas to push them into
+const MAX_PUSH: i32 = 32;
+// INFO: Elements this hard (solid, source) can not be pushed by pistons
+const UNPUSHABLE: f32 = 1000.;
+
+/*
+    INFO: Logic cells are not conductors themselves, they read the conductors around them and spark the ones they output to.
+    A logic cell that sees one of its inputs sparking sets its charge to HOLD, electricity.rs counts it down,
+    so a charge above zero means the cell is powered even in the frames between two pulses.
+    Signals go through gates from left to right: the conductors on the left, above and below are the inputs,
+    the conductor on the right is the output
+*/
+fn is_powered(x: i32, y: i32, future_grid: &mut Grid, mov_dt: &mut MovData) -> bool {
+    let cell = get(x, y, future_grid, mov_dt);
+    cell.charge > 0 && registry().get(cell.element).conducts_electricity
+}
+
+fn hold(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
+    future_grid[i][j].charge = HOLD;
+    *mov_dt.keep_active = true;
+    mov_dt.dirty_rect.set_temp(i, j);
+}
+
+fn output(x: i32, y: i32, future_grid: &mut Grid, mov_dt: &mut MovData) {
+    if registry().get(get(x, y, future_grid, mov_dt).element).conducts_electricity {
+        spark(x, y, future_grid, mov_dt);
+    }
+}
+
+// INFO: Sets a cell and rebuilds the terrain colliders if a chunk collider cell appeared, disappeared or moved
+fn replace(x: i32, y: i32, cell: Cell, future_grid: &mut Grid, mov_dt: &mut MovData) {
+    let old = get(x, y, future_grid, mov_dt);
+    if old.collider_type == ElColliderType::Chunk || cell.collider_type == ElColliderType::Chunk {
+        set_colliders_dirty(x, y, mov_dt);
+    }
+    set(x, y, future_grid, mov_dt, cell);
+}
+
+/*
+    INFO: Sparks the conductors around it while the element it senses touches it.
+    A sensor of Air is not set up yet, it starts sensing the first moving element that touches it, like sources do
+*/
+pub fn sense(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, element: Element) {
+    let neighbors = [(i as i32, j as i32 - 1), (i as i32, j as i32 + 1), (i as i32 - 1, j as i32), (i as i32 + 1, j as i32)];
+
+    if element == Element::Air {
+        for (x, y) in neighbors {
+            let other = get(x, y, future_grid, mov_dt).element;
+            if other != Element::Air && registry().get(other).movement != Movement::Static {
+                future_grid[i][j].action = Some(Action::Sense(other));
+                break;
+            }
+        }
+        return;
+    }
+
+    if neighbors.iter().any(|&(x, y)| get(x, y, future_grid, mov_dt).element == element) {
+        for (x, y) in neighbors {
+            output(x, y, future_grid, mov_dt);
+        }
+        *mov_dt.keep_active = true;
+        mov_dt.dirty_rect.set_temp(i, j);
+    }
+}
+
+pub fn gate(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, kind: LogicGate) {
+    let (x, y) = (i as i32, j as i32);
+    let mut connected = 0;
+    let mut powered = 0;
+    for (ix, iy) in [(x - 1, y), (x, y - 1), (x, y + 1)] {
+        if registry().get(get(ix, iy, future_grid, mov_dt).element).conducts_electricity {
+            connected += 1;
+            if is_powered(ix, iy, future_grid, mov_dt) {
+                powered += 1;
+            }
+        }
+    }
+
+    let inputs = match kind {
+        LogicGate::And => connected > 0 && powered == connected,
+        LogicGate::Or | LogicGate::Not | LogicGate::Delay => powered > 0,
+    };
+    if inputs {
+        hold(future_grid, i, j, mov_dt);
+    }
+    let held = future_grid[i][j].charge > 0;
+
+    let on = match kind {
+        LogicGate::And | LogicGate::Or => held,
+        LogicGate::Not => !held,
+        // INFO: The lifetime of a delay gate is a shift register of whether it was powered in each of the last frames
+        LogicGate::Delay => {
+            let history = ((future_grid[i][j].lifetime as u32) << 1 | held as u32) & ((1 << (DELAY + 1)) - 1);
+            future_grid[i][j].lifetime = history as i32;
+            if history != 0 {
+                *mov_dt.keep_active = true;
+                mov_dt.dirty_rect.set_temp(i, j);
+            }
+            history >> DELAY & 1 == 1
+        }
+    };
+
+    if on {
+        output(x + 1, y, future_grid, mov_dt);
+        *mov_dt.keep_active = true;
+        mov_dt.dirty_rect.set_temp(i, j);
+    }
+}
+
+fn motor_powered(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
+    let (x, y) = (i as i32, j as i32);
+    if [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)].into_iter().any(|(x, y)| is_powered(x, y, future_grid, mov_dt)) {
+        hold(future_grid, i, j, mov_dt);
+    }
+    future_grid[i][j].charge > 0
+}
+
+/*
+    INFO: Door motors open the door cells stacked on top of them while they are powered by turning them into air,
+    and close them again one cell per frame from the bottom once the power is gone. A door can only close into gas,
+    anything standing in the doorway keeps it open. The lifetime is how many cells of the door are open
+*/
+pub fn door(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, panel: Element) {
+    let (x, y) = (i as i32, j as i32);
+    let open = future_grid[i][j].lifetime.max(0);
+
+    if motor_powered(future_grid, i, j, mov_dt) {
+        let mut length = 0;
+        while length < MAX_DOOR && get(x, y - length - 1, future_grid, mov_dt).element == panel {
+            replace(x, y - length - 1, air_element(), future_grid, mov_dt);
+            length += 1;
+        }
+        future_grid[i][j].lifetime = open.max(length);
+        return;
+    }
+
+    if open == 0 {
+        return;
+    }
+
+    *mov_dt.keep_active = true;
+    mov_dt.dirty_rect.set_temp(i, j);
+
+    let Some(k) = (1..=open).find(|k| get(x, y - k, future_grid, mov_dt).element != panel) else {
+        future_grid[i][j].lifetime = 0;
+        return;
+    };
+    if get(x, y - k, future_grid, mov_dt).state == State::Gas {
+        replace(x, y - k, el_from_enum(panel), future_grid, mov_dt);
+    }
+}
+
+/*
+    INFO: Pistons extend an arm upwards one cell per frame while they are powered and pull it back once the power is gone.
+    The arm pushes the column of cells in front of it up into the first gas above them, it is blocked if there is none
+    or something unpushable is in the way. The lifetime is how far the arm is extended
+*/
+pub fn piston(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, arm: Element) {
+    let (x, y) = (i as i32, j as i32);
+    let extension = future_grid[i][j].lifetime.max(0);
+
+    if motor_powered(future_grid, i, j, mov_dt) {
+        if extension >= MAX_EXTENSION {
+            return;
+        }
+
+        let head = y - extension - 1;
+        let mut gap = None;
+        for k in 0..MAX_PUSH {
+            let cell = get(x, head - k, future_grid, mov_dt);
+            if cell.state == State::Gas {
+                gap = Some(k);
+                break;
+            }
+            if registry().get(cell.element).hardness >= UNPUSHABLE {
+                break;
+            }
+        }
+        let Some(gap) = gap else {
+            return;
+        };
+
+        for k in (0..gap).rev() {
+            let pushed = get(x, head - k, future_grid, mov_dt);
+            replace(x, head - k - 1, pushed, future_grid, mov_dt);
+        }
+        replace(x, head, el_from_enum(arm), future_grid, mov_dt);
+        future_grid[i][j].lifetime = extension + 1;
+        return;
+    }
+
+    if extension == 0 {
+        return;
+    }
+
+    *mov_dt.keep_active = true;
+    mov_dt.dirty_rect.set_temp(i, j);
+
+    if get(x, y - extension, future_grid, mov_dt).element == arm {
+        replace(x, y - extension, air_element(), future_grid, mov_dt);
+    }
+    future_grid[i][j].lifetime = extension - 1;
+}
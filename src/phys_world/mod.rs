@@ -1,11 +1,28 @@
 pub mod chunk_manager;
 pub mod chunk;
+pub mod chunk_index;
+pub mod creatures;
 pub mod base_movement;
+pub mod electricity;
 pub mod element;
+pub mod element_registry;
 pub mod el_movement;
 pub mod element_actions;
 pub mod element_texture_handler;
+pub mod erosion;
+pub mod explosion;
+pub mod heat;
+pub mod liquid_level;
+pub mod logic;
+pub mod plants;
+pub mod pressure;
 pub mod rapier_edge_gen;
 pub mod rapier_world_handler;
 pub mod all_physics_manager;
 pub mod rigid_sand_body;
+pub mod scenario;
+pub mod support;
+pub mod weather;
+pub mod wetness;
+pub mod world_image;
+pub mod world_save;
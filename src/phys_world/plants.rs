@@ -0,0 +1,331 @@
+use std::sync::OnceLock;
+
+use super::{
+    base_movement::{get, set, set_colliders_dirty},
+    chunk::{Grid, MovData},
+    element::{air_element, dirt_element, el_from_enum, grass_element, Action, Cell, ElColliderType, Element, Growth, State},
+    element_registry::registry,
+};
+
+// INFO: Water a seed looks for around it to germinate and the most water cells it drinks doing so
+const MOIST_RADIUS: i32 = 5;
+const MAX_DRINK: usize = 6;
+// INFO: Cells a plant grows for every water cell its seed drank
+const WATER_ENERGY: i32 = 6;
+// INFO: Chances per frame that a seed on moist soil germinates and that a growing tip grows one cell
+const GERMINATE_CHANCE: f32 = 0.05;
+const GROW_CHANCE: f32 = 0.1;
+// INFO: Trees branch off every BRANCH_EVERY cells of their trunk, the radius of the leaf crowns on top of trunks and branches
+const BRANCH_EVERY: i32 = 4;
+const TRUNK_CROWN: i32 = 4;
+const BRANCH_CROWN: i32 = 2;
+// INFO: Seeds a finished tree drops from its crown
+const TREE_SEEDS: usize = 2;
+// INFO: Chance that a vine puts out a leaf next to every cell it grows
+const VINE_LEAF_CHANCE: f32 = 0.2;
+
+/*
+    INFO: The elements plants are made of, looked up by name once.
+    Definitions files without them only have grass, seeds of such files never germinate
+*/
+struct PlantParts {
+    seed: Element,
+    trunk: Element,
+    leaf: Element,
+    vine: Element,
+}
+
+fn parts() -> Option<&'static PlantParts> {
+    static PARTS: OnceLock<Option<PlantParts>> = OnceLock::new();
+    PARTS
+        .get_or_init(|| {
+            let registry = registry();
+            Some(PlantParts {
+                seed: registry.by_name("Seed")?,
+                trunk: registry.by_name("Trunk")?,
+                leaf: registry.by_name("Leaf")?,
+                vine: registry.by_name("Vine")?,
+            })
+        })
+        .as_ref()
+}
+
+/*
+    INFO: Every plant cell that still grows has a Grow action with its rule set, the lifetime of growing tips is the energy they have left.
+    Tips that burn turn into fire with their Grow action, tips that get buried stop growing
+*/
+pub fn grow(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, growth: Growth) {
+    match growth {
+        Growth::Grass => grass(future_grid, i, j, mov_dt),
+        Growth::Seed => seed(future_grid, i, j, mov_dt),
+        Growth::Tree => tree(future_grid, i, j, mov_dt),
+        Growth::Branch(dx) => branch(future_grid, i, j, mov_dt, dx as i32),
+        Growth::Vine => vine(future_grid, i, j, mov_dt),
+    }
+}
+
+fn keep_growing(i: usize, j: usize, mov_dt: &mut MovData) {
+    *mov_dt.keep_active = true;
+    mov_dt.dirty_rect.set_temp(i, j);
+}
+
+// INFO: The cell is done growing and becomes a plain cell of its element
+fn stop(future_grid: &mut Grid, i: usize, j: usize) {
+    future_grid[i][j].action = None;
+    future_grid[i][j].lifetime = -1;
+}
+
+fn tip(element: Element, growth: Growth, energy: i32) -> Cell {
+    Cell {
+        action: Some(Action::Grow(growth)),
+        lifetime: energy,
+        ..el_from_enum(element)
+    }
+}
+
+fn is_free(cell: &Cell) -> bool {
+    cell.state == State::Gas && cell.element != Element::Fire
+}
+
+fn is_soil(element: Element) -> bool {
+    element == Element::Dirt || element == Element::SolidDirt || element == Element::Grass
+}
+
+fn place(x: i32, y: i32, cell: Cell, future_grid: &mut Grid, mov_dt: &mut MovData) {
+    if cell.collider_type == ElColliderType::Chunk {
+        set_colliders_dirty(x, y, mov_dt);
+    }
+    set(x, y, future_grid, mov_dt, cell);
+}
+
+// INFO: Grass spreads over the surface of solid dirt and grows blades of up to 6 cells
+fn grass(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
+    let up = get(i as i32, j as i32 - 1, future_grid, mov_dt);
+    let down = get(i as i32, j as i32 + 1, future_grid, mov_dt);
+    let left = get(i as i32 - 1, j as i32, future_grid, mov_dt);
+    let right = get(i as i32 + 1, j as i32, future_grid, mov_dt);
+
+    let up_right = get(i as i32 + 1, j as i32 - 1, future_grid, mov_dt);
+    let up_left = get(i as i32 - 1, j as i32 - 1, future_grid, mov_dt);
+    let down_right = get(i as i32 + 1, j as i32 + 1, future_grid, mov_dt);
+    let down_left = get(i as i32 - 1, j as i32 + 1, future_grid, mov_dt);
+
+    let mut active = false;
+    if left.element == Element::Air && down_left.element == Element::SolidDirt {
+        set(i as i32 - 1, j as i32, future_grid, mov_dt, grass_element());
+        active = true;
+    }
+    if right.element == Element::Air && down_right.element == Element::SolidDirt {
+        set(i as i32 + 1, j as i32, future_grid, mov_dt, grass_element());
+        active = true;
+    }
+    if down.element == Element::SolidDirt {
+        if down_right.element == Element::Air {
+            set(i as i32 + 1, j as i32 + 1, future_grid, mov_dt, grass_element());
+            active = true;
+        }
+        if down_left.element == Element::Air {
+            set(i as i32 - 1, j as i32 + 1, future_grid, mov_dt, grass_element());
+            active = true;
+        }
+    }
+    if down.element == Element::Air && (down_right.element == Element::SolidDirt || down_left.element == Element::SolidDirt) {
+        set(i as i32, j as i32 + 1, future_grid, mov_dt, grass_element());
+        active = true;
+    }
+    if up.element == Element::Air && (up_right.element == Element::SolidDirt || up_left.element == Element::SolidDirt) {
+        set(i as i32, j as i32 - 1, future_grid, mov_dt, grass_element());
+        active = true;
+    }
+    if right.element == Element::SolidDirt && up_right.element == Element::Air {
+        set(i as i32 + 1, j as i32 - 1, future_grid, mov_dt, grass_element());
+        active = true;
+    }
+    if left.element == Element::SolidDirt && up_left.element == Element::Air {
+        set(i as i32 - 1, j as i32 - 1, future_grid, mov_dt, grass_element());
+        active = true;
+    }
+
+    let mut height = 1;
+    let growth_chance = mov_dt.rng.f32();
+    while growth_chance < 1_f32 / (height as f32).powf(2.) {
+        if get(i as i32, j as i32 - height, future_grid, mov_dt).element != Element::Air || height >= 6 {
+            break;
+        }
+        let mut grass_el = grass_element();
+        grass_el.action = None;
+        set(i as i32, j as i32 - height, future_grid, mov_dt, grass_el);
+        height += 1;
+    }
+
+    if !active {
+        future_grid[i][j].action = None;
+    }
+}
+
+/*
+    INFO: Seeds germinate when they lie on soil with water close by, they drink the water around them and the more they get the bigger the plant.
+    Next to a wall a seed grows into a vine that climbs it, otherwise into a tree. A buried seed rots into dirt once its lifetime runs out
+*/
+fn seed(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
+    let Some(parts) = parts() else {
+        return;
+    };
+    let (x, y) = (i as i32, j as i32);
+
+    if !is_free(&get(x, y - 1, future_grid, mov_dt)) {
+        keep_growing(i, j, mov_dt);
+        future_grid[i][j].lifetime -= 1;
+        if future_grid[i][j].lifetime <= 0 {
+            set(x, y, future_grid, mov_dt, dirt_element());
+        }
+        return;
+    }
+
+    if !is_soil(get(x, y + 1, future_grid, mov_dt).element) {
+        return;
+    }
+
+    let mut water = vec![];
+    for dx in -MOIST_RADIUS..=MOIST_RADIUS {
+        for dy in -MOIST_RADIUS..=MOIST_RADIUS {
+            if get(x + dx, y + dy, future_grid, mov_dt).element == Element::Water {
+                water.push((x + dx, y + dy));
+            }
+        }
+    }
+    if water.is_empty() {
+        return;
+    }
+
+    keep_growing(i, j, mov_dt);
+    if mov_dt.rng.f32() >= GERMINATE_CHANCE {
+        return;
+    }
+
+    mov_dt.rng.shuffle(&mut water);
+    water.truncate(MAX_DRINK);
+    for &(wx, wy) in &water {
+        set(wx, wy, future_grid, mov_dt, air_element());
+    }
+    let energy = water.len() as i32 * WATER_ENERGY;
+
+    let climbable = [x - 1, x + 1].into_iter().any(|side| get(side, y - 1, future_grid, mov_dt).state == State::Solid);
+    let sprout = if climbable { tip(parts.vine, Growth::Vine, energy * 2) } else { tip(parts.trunk, Growth::Tree, energy) };
+    place(x, y, sprout, future_grid, mov_dt);
+}
+
+// INFO: Grows the trunk up, branching off to the sides on the way, and puts a leaf crown on top once the energy is used up or something is in the way
+fn tree(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
+    let Some(parts) = parts() else {
+        return;
+    };
+    let (x, y) = (i as i32, j as i32);
+    let energy = future_grid[i][j].lifetime;
+
+    keep_growing(i, j, mov_dt);
+    if mov_dt.rng.f32() >= GROW_CHANCE {
+        return;
+    }
+
+    let up = get(x, y - 1, future_grid, mov_dt);
+    if energy <= 0 || !is_free(&up) {
+        stop(future_grid, i, j);
+        crown(x, y - 1, TRUNK_CROWN, parts, future_grid, mov_dt);
+        for _ in 0..TREE_SEEDS {
+            let (sx, sy) = (x + mov_dt.rng.i32(-TRUNK_CROWN..=TRUNK_CROWN), y - 1 + mov_dt.rng.i32(0..=1));
+            if is_free(&get(sx, sy, future_grid, mov_dt)) || get(sx, sy, future_grid, mov_dt).element == parts.leaf {
+                set(sx, sy, future_grid, mov_dt, el_from_enum(parts.seed));
+            }
+        }
+        return;
+    }
+
+    stop(future_grid, i, j);
+    place(x, y - 1, tip(parts.trunk, Growth::Tree, energy - 1), future_grid, mov_dt);
+
+    if energy > BRANCH_EVERY && energy % BRANCH_EVERY == 0 {
+        let dx = if mov_dt.rng.bool() { 1 } else { -1 };
+        if is_free(&get(x + dx, y - 1, future_grid, mov_dt)) {
+            place(x + dx, y - 1, tip(parts.trunk, Growth::Branch(dx as i8), energy / 3), future_grid, mov_dt);
+        }
+    }
+}
+
+// INFO: Branches grow to their side and up in turns and end in a small crown
+fn branch(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData, dx: i32) {
+    let Some(parts) = parts() else {
+        return;
+    };
+    let (x, y) = (i as i32, j as i32);
+    let energy = future_grid[i][j].lifetime;
+
+    keep_growing(i, j, mov_dt);
+    if mov_dt.rng.f32() >= GROW_CHANCE {
+        return;
+    }
+
+    let (nx, ny) = if energy % 2 == 0 { (x + dx, y) } else { (x + dx, y - 1) };
+    stop(future_grid, i, j);
+    if energy <= 0 || !is_free(&get(nx, ny, future_grid, mov_dt)) {
+        crown(x, y, BRANCH_CROWN, parts, future_grid, mov_dt);
+        return;
+    }
+    place(nx, ny, tip(parts.trunk, Growth::Branch(dx as i8), energy - 1), future_grid, mov_dt);
+}
+
+fn crown(x: i32, y: i32, radius: i32, parts: &PlantParts, future_grid: &mut Grid, mov_dt: &mut MovData) {
+    for dx in -radius..=radius {
+        for dy in -radius..=radius {
+            if dx * dx + dy * dy <= radius * radius && is_free(&get(x + dx, y + dy, future_grid, mov_dt)) {
+                set(x + dx, y + dy, future_grid, mov_dt, el_from_enum(parts.leaf));
+            }
+        }
+    }
+}
+
+/*
+    INFO: Vines climb up along anything solid and spread sideways or down over it when they can not climb further,
+    with nothing to hold on to they grow straight up. They drop a seed when they are done
+*/
+fn vine(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) {
+    let Some(parts) = parts() else {
+        return;
+    };
+    let (x, y) = (i as i32, j as i32);
+    let energy = future_grid[i][j].lifetime;
+
+    keep_growing(i, j, mov_dt);
+    if mov_dt.rng.f32() >= GROW_CHANCE {
+        return;
+    }
+
+    let mut sides = [(x - 1, y), (x + 1, y)];
+    mov_dt.rng.shuffle(&mut sides);
+    let target = [(x, y - 1), sides[0], sides[1], (x, y + 1)]
+        .into_iter()
+        .find(|&(nx, ny)| {
+            is_free(&get(nx, ny, future_grid, mov_dt))
+                && [(nx, ny - 1), (nx, ny + 1), (nx - 1, ny), (nx + 1, ny)].into_iter().any(|(ax, ay)| {
+                    let other = get(ax, ay, future_grid, mov_dt);
+                    (ax, ay) != (x, y) && other.state == State::Solid && other.element != parts.vine && other.element != parts.leaf
+                })
+        })
+        .or_else(|| is_free(&get(x, y - 1, future_grid, mov_dt)).then_some((x, y - 1)));
+
+    stop(future_grid, i, j);
+    let Some((nx, ny)) = target.filter(|_| energy > 0) else {
+        if let Some((sx, sy)) = [(x, y + 1), (x - 1, y), (x + 1, y)].into_iter().find(|&(sx, sy)| is_free(&get(sx, sy, future_grid, mov_dt))) {
+            set(sx, sy, future_grid, mov_dt, el_from_enum(parts.seed));
+        }
+        return;
+    };
+
+    place(nx, ny, tip(parts.vine, Growth::Vine, energy - 1), future_grid, mov_dt);
+    if mov_dt.rng.f32() < VINE_LEAF_CHANCE {
+        let (lx, ly) = sides[1];
+        if is_free(&get(lx, ly, future_grid, mov_dt)) {
+            set(lx, ly, future_grid, mov_dt, el_from_enum(parts.leaf));
+        }
+    }
+}
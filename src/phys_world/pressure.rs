@@ -0,0 +1,275 @@
+use glam::Vec2;
+
+use super::{
+    chunk::{self, Chunk, Grid, COLS, ROWS},
+    chunk_index::ChunkIndex,
+    chunk_manager::WorldChunks,
+    element::State,
+};
+
+// INFO: The pressure field is a lot coarser than the cells, every block of BLOCK x BLOCK cells shares one pressure and air velocity
+pub const BLOCK: usize = 4;
+pub const BLOCKS_X: usize = COLS / BLOCK;
+pub const BLOCKS_Y: usize = ROWS / BLOCK;
+
+// INFO: Part of the pressure difference that flows through a fully open side of a block each frame
+const FLOW_RATE: f32 = 0.2;
+// INFO: Air velocity a flow of one unit of pressure gives, the velocity of the last frames fades with DAMPING
+const FLOW_SPEED: f32 = 1.;
+const DAMPING: f32 = 0.8;
+// INFO: Air is never sealed in completely, pressure slowly leaks away so the world settles down after an explosion
+const LEAK: f32 = 0.995;
+// INFO: Flows and pressures below this are considered settled
+const SETTLED: f32 = 0.01;
+// INFO: Air faster than this pushes the cells in its block around
+const PUSH_SPEED: f32 = 0.5;
+// INFO: Elements this dense or lighter move with the air completely, heavier ones only partly
+const AIR_WEIGHT: f32 = 10.;
+
+// INFO: Pressure a gas cell adds when it appears (boiling, burning), so gas in a closed room builds up pressure
+pub const GAS_PRESSURE: f32 = 1.;
+// INFO: Pressure an explosion of force 1 adds to every block it covers
+pub const EXPLOSION_PRESSURE: f32 = 5.;
+// INFO: A block does not take more pressure than this, a lot of explosives going off together would take forever to settle
+const MAX_PRESSURE: f32 = 50.;
+
+/*
+    INFO: Pressure and air velocity per block of a chunk, blocks are stored column by column like the cells.
+    Pressure flows between neighboring blocks through the cells that are not solid, so walls hold it in,
+    and the flow is what moves the air that gases and light powders get carried by
+*/
+#[derive(Clone)]
+pub struct PressureField {
+    pub pressure: Vec<f32>,
+    pub velocity: Vec<Vec2>,
+    // INFO: Pressure is still flowing, the field gets updated even if the cells of the chunk are asleep
+    pub flowing: bool,
+    // INFO: Some block has pressure, a sealed room can hold it without flowing until it gets opened
+    pub pressurized: bool,
+}
+
+impl Default for PressureField {
+    fn default() -> Self {
+        Self {
+            pressure: vec![0.; BLOCKS_X * BLOCKS_Y],
+            velocity: vec![Vec2::ZERO; BLOCKS_X * BLOCKS_Y],
+            flowing: false,
+            pressurized: false,
+        }
+    }
+}
+
+fn block_of(i: usize, j: usize) -> usize {
+    (i / BLOCK) * BLOCKS_Y + j / BLOCK
+}
+
+pub fn add_pressure(field: &mut PressureField, i: usize, j: usize, amount: f32) {
+    let block = block_of(i, j);
+    field.pressure[block] = (field.pressure[block] + amount).min(MAX_PRESSURE);
+    field.flowing = true;
+    field.pressurized = true;
+}
+
+pub fn air_velocity(field: &PressureField, i: usize, j: usize) -> Vec2 {
+    field.velocity[block_of(i, j)]
+}
+
+// INFO: Moves the velocity of a cell towards the air around it, the lighter the element the more it follows the air
+pub fn push_cell(future_grid: &mut Grid, i: usize, j: usize, field: &PressureField) {
+    if !field.flowing {
+        return;
+    }
+
+    let air = air_velocity(field, i, j);
+    if air.length_squared() < PUSH_SPEED * PUSH_SPEED {
+        return;
+    }
+
+    let cell = &mut future_grid[i][j];
+    let weight = (AIR_WEIGHT / cell.density.max(1.)).min(1.) * 0.5;
+    cell.velocity = cell.velocity.lerp(air, weight);
+}
+
+// INFO: Explosions push the air away from them instead of the cells, whatever the air carries flies with it
+pub fn explode(chunk: &mut Chunk, i: i32, j: i32, radius: i32, force: f32) {
+    explode_field(&mut chunk.pressure, i, j, radius, force);
+}
+
+pub fn explode_field(field: &mut PressureField, i: i32, j: i32, radius: i32, force: f32) {
+    let radius = radius as f32 / 2.;
+    for bx in 0..BLOCKS_X {
+        for by in 0..BLOCKS_Y {
+            let center = Vec2::new((bx * BLOCK + BLOCK / 2) as f32, (by * BLOCK + BLOCK / 2) as f32);
+            if center.distance(Vec2::new(i as f32, j as f32)) <= radius.max(BLOCK as f32) {
+                add_pressure(field, bx * BLOCK, by * BLOCK, force * EXPLOSION_PRESSURE);
+            }
+        }
+    }
+}
+
+/*
+    INFO: How open the side between a block and its right (or bottom) neighbor is, the part of the rows (or columns)
+    that have no solid cell between the centers of the two blocks. Every cell lies between the centers of exactly one pair,
+    so a wall closes it no matter how thin it is or where in the block it is
+*/
+fn side_openness(cell_solid: impl Fn(i32, i32) -> bool, block: (i32, i32), horizontal: bool) -> f32 {
+    let (x, y) = (block.0 * BLOCK as i32, block.1 * BLOCK as i32);
+    let half = BLOCK as i32 / 2;
+    let mut open = 0;
+    for n in 0..BLOCK as i32 {
+        let blocked = (half..half + BLOCK as i32).any(|m| {
+            if horizontal {
+                cell_solid(x + m, y + n)
+            } else {
+                cell_solid(x + n, y + m)
+            }
+        });
+        if !blocked {
+            open += 1;
+        }
+    }
+    open as f32 / BLOCK as f32
+}
+
+fn is_solid(chunks: &WorldChunks, index: (i32, i32), x: i32, y: i32) -> bool {
+    let chunk_index = (index.0 + x.div_euclid(COLS as i32), index.1 + y.div_euclid(ROWS as i32));
+    match chunks.get(&chunk_index) {
+        Some(chunk) => chunk.grid[x.rem_euclid(COLS as i32) as usize][y.rem_euclid(ROWS as i32) as usize].state == State::Solid,
+        // INFO: Pressure does not flow into the unloaded world
+        None => true,
+    }
+}
+
+/*
+    INFO: One step of the pressure fields of the chunks that are flowing, or are awake and hold pressure.
+    All flows are worked out from the pressures of the last step first and applied afterwards,
+    so the result does not depend on the order of the chunks.
+    Returns the chunks that got woken up by the air moving their cells
+*/
+pub fn update_pressure(chunks: &mut WorldChunks, chunk_index: &ChunkIndex) -> Vec<(i32, i32)> {
+    let updated: Vec<(i32, i32)> = chunk_index
+        .iter()
+        .filter(|index| {
+            let chunk = &chunks[index];
+            chunk.pressure.flowing || (chunk.active && chunk.pressure.pressurized)
+        })
+        .collect();
+
+    if updated.is_empty() {
+        return vec![];
+    }
+
+    // INFO: (chunk, block, pressure change, velocity change)
+    let mut flows: Vec<((i32, i32), usize, f32, Vec2)> = vec![];
+    let is_updated = |index: (i32, i32)| updated.binary_search_by(|other| (other.1, other.0).cmp(&(index.1, index.0))).is_ok();
+
+    for index in &updated {
+        let solid = |x: i32, y: i32| is_solid(chunks, *index, x, y);
+        let pressure = &chunks[index].pressure.pressure;
+
+        for bx in -1..BLOCKS_X as i32 {
+            for by in -1..BLOCKS_Y as i32 {
+                // INFO: The right and bottom side of every block, plus the sides to neighbors that do not get updated themselves
+                for (dx, dy) in [(1, 0), (0, 1)] {
+                    let (nx, ny) = (bx + dx, by + dy);
+                    if bx < 0 && dx == 0 || by < 0 && dy == 0 {
+                        continue;
+                    }
+                    // INFO: A side to the left or top neighbor belongs to the neighbor if it gets updated too
+                    if (bx < 0 || by < 0) && is_updated((index.0 - (bx < 0) as i32, index.1 - (by < 0) as i32)) {
+                        continue;
+                    }
+
+                    let a = block_in(chunks, *index, bx, by);
+                    let b = block_in(chunks, *index, nx, ny);
+                    let (Some(a), Some(b)) = (a, b) else {
+                        continue;
+                    };
+
+                    let openness = side_openness(solid, (bx, by), dx == 1);
+                    if openness == 0. {
+                        continue;
+                    }
+
+                    let a_pressure = if a.0 == *index { pressure[a.1] } else { chunks[&a.0].pressure.pressure[a.1] };
+                    let b_pressure = if b.0 == *index { pressure[b.1] } else { chunks[&b.0].pressure.pressure[b.1] };
+                    let flow = FLOW_RATE * openness * (a_pressure - b_pressure);
+                    if flow.abs() < SETTLED * SETTLED {
+                        continue;
+                    }
+
+                    let velocity = Vec2::new(dx as f32, dy as f32) * flow * FLOW_SPEED;
+                    flows.push((a.0, a.1, -flow, velocity));
+                    flows.push((b.0, b.1, flow, velocity));
+                }
+            }
+        }
+    }
+
+    for index in &updated {
+        if let Some(chunk) = chunks.get_mut(index) {
+            for velocity in chunk.pressure.velocity.iter_mut() {
+                *velocity *= DAMPING;
+            }
+            for pressure in chunk.pressure.pressure.iter_mut() {
+                *pressure *= LEAK;
+            }
+            chunk.pressure.flowing = false;
+        }
+    }
+
+    // INFO: Every chunk the flows reached gets settled below, so no pressure is left behind without the flags knowing about it
+    let mut touched = updated.clone();
+    for (index, block, pressure, velocity) in flows {
+        touched.push(index);
+        if let Some(chunk) = chunks.get_mut(&index) {
+            chunk.pressure.pressure[block] += pressure;
+            chunk.pressure.velocity[block] += velocity;
+            if pressure.abs() >= SETTLED {
+                chunk.pressure.flowing = true;
+            }
+        }
+    }
+
+    touched.sort_by_key(|index| (index.1, index.0));
+    touched.dedup();
+
+    let mut woken = vec![];
+    for index in touched {
+        let Some(chunk) = chunks.get_mut(&index) else {
+            continue;
+        };
+        let field = &mut chunk.pressure;
+        field.pressurized = field.pressure.iter().any(|pressure| pressure.abs() >= SETTLED);
+        if field.velocity.iter().any(|velocity| velocity.length_squared() >= SETTLED * SETTLED) {
+            field.flowing = true;
+        } else if !field.pressurized {
+            field.pressure.iter_mut().for_each(|pressure| *pressure = 0.);
+            field.velocity.iter_mut().for_each(|velocity| *velocity = Vec2::ZERO);
+        }
+
+        // INFO: Air that moves fast enough wakes up the cells it blows through
+        let mut wake = vec![];
+        for (block, velocity) in field.velocity.iter().enumerate() {
+            if velocity.length_squared() >= PUSH_SPEED * PUSH_SPEED {
+                wake.push(((block / BLOCKS_Y) * BLOCK + BLOCK / 2, (block % BLOCKS_Y) * BLOCK + BLOCK / 2));
+            }
+        }
+        if !wake.is_empty() {
+            for (x, y) in wake {
+                chunk::wake_up(chunk, x, y);
+            }
+            woken.push(index);
+        }
+    }
+    woken
+}
+
+// INFO: The chunk and block a block position relative to a chunk is in, blocks outside of the chunk are in its neighbors
+fn block_in(chunks: &WorldChunks, index: (i32, i32), bx: i32, by: i32) -> Option<((i32, i32), usize)> {
+    let chunk_index = (index.0 + bx.div_euclid(BLOCKS_X as i32), index.1 + by.div_euclid(BLOCKS_Y as i32));
+    if !chunks.contains_key(&chunk_index) {
+        return None;
+    }
+    Some((chunk_index, bx.rem_euclid(BLOCKS_X as i32) as usize * BLOCKS_Y + by.rem_euclid(BLOCKS_Y as i32) as usize))
+}
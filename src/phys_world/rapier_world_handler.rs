@@ -1,13 +1,16 @@
 use ahash::HashMap;
-use notan::{draw::{Draw, DrawShapes, DrawTransform}, prelude::App};
 use rapier2d::prelude::*;
 
-use crate::{phys_world::chunk::{COLS, ROWS}, debug_ui::DebugInfo, camera::Camera2D};
+use crate::phys_world::chunk::{COLS, ROWS};
 
-use super::{chunk_manager::WorldChunks, chunk::UPSCALE_FACTOR, rigid_sand_body::RigidSandBody, element_texture_handler::ElementTexHandler};
+use super::{chunk_manager::WorldChunks, chunk::UPSCALE_FACTOR, explosion::Explosion, rigid_sand_body::RigidSandBody, element_texture_handler::ElementTexHandler};
 
 pub const PHYS_SCALE: f32 = 50.0;
 pub const GRAVITY: f32 = 9.81;
+// INFO: Speed change (m/s) an explosion of force 1 gives a body right at its center, bodies are pushed up to twice the blast radius away
+const EXPLOSION_IMPULSE: f32 = 1.5;
+// INFO: A lot of explosives going off at once would throw bodies out of the world
+const MAX_EXPLOSION_SPEED: f32 = 25.;
 
 pub struct RapierHandler {
 	pub rigid_body_set: RigidBodySet,
@@ -27,10 +30,16 @@ pub struct RapierHandler {
 	pub update_phys: bool,
 	pub rigid_sand_bodies: Vec<RigidSandBody>,
 	
-	ball_body_handles: Vec<RigidBodyHandle>,
+	pub ball_body_handles: Vec<RigidBodyHandle>,
 	pub select_body: SelectBody
 }
 
+impl Default for RapierHandler {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl RapierHandler {
 	pub fn new() -> Self {
 		let rigid_body_set = RigidBodySet::new();
@@ -75,9 +84,6 @@ impl RapierHandler {
 	    }
 	}
 
-	pub fn update(&mut self, _app: &mut App, _camera: &Camera2D) {
-	}
-
 	pub fn update_fixed(&mut self) {
 		if !self.update_phys {
 			return;
@@ -100,6 +106,33 @@ impl RapierHandler {
 		);
 	}
 
+	// INFO: Pushes the dynamic bodies around an explosion away from it, the closer they are the harder
+	pub fn apply_explosion(&mut self, explosion: &Explosion) {
+		let center = vector![explosion.position.x, explosion.position.y] * UPSCALE_FACTOR / PHYS_SCALE;
+		let reach = explosion.radius * 2. * UPSCALE_FACTOR / PHYS_SCALE;
+
+		for (_, body) in self.rigid_body_set.iter_mut() {
+			if !body.is_dynamic() {
+				continue;
+			}
+
+			let offset = body.translation() - center;
+			let dist = offset.norm();
+			if dist >= reach {
+				continue;
+			}
+
+			let dir = if dist > 0. { offset / dist } else { vector![0., -1.] };
+			let impulse = dir * explosion.force * (1. - dist / reach) * EXPLOSION_IMPULSE * body.mass();
+			body.apply_impulse(impulse, true);
+
+			let velocity = *body.linvel();
+			if velocity.norm() > MAX_EXPLOSION_SPEED {
+				body.set_linvel(velocity.normalize() * MAX_EXPLOSION_SPEED, true);
+			}
+		}
+	}
+
 	pub fn add_ball(&mut self, mouse: (f32, f32)) {
 		let rigid_body = RigidBodyBuilder::dynamic().translation(vector![mouse.0 / PHYS_SCALE, mouse.1 / PHYS_SCALE]).build();
 		let collider = ColliderBuilder::ball(8. / PHYS_SCALE).restitution(0.7).build();
@@ -125,14 +158,34 @@ impl RapierHandler {
 		self.rigid_sand_bodies.clear();
 	}
 
+	// INFO: Removes every body and chunk collider, the rapier world is empty afterwards
+	pub fn clear_world(&mut self) {
+		self.remove_balls();
+		self.remove_sand_bodies();
+
+		let indices: Vec<(i32, i32)> = self.chunk_colliders.keys().copied().collect();
+		for index in indices {
+			self.remove_chunk_colliders(index);
+		}
+	}
+
+	pub fn remove_chunk_colliders(&mut self, index: (i32, i32)) {
+		if let Some(collider_handles) = self.chunk_colliders.remove(&index) {
+			for collider_handle in collider_handles {
+				self.collider_set.remove(collider_handle, &mut self.island_manager, &mut self.rigid_body_set, false);
+			}
+		}
+	}
+
 	pub fn create_chunk_colliders(&mut self, chunks: &mut WorldChunks) {
-		for (index, chunk) in chunks.iter_mut() {
+		// INFO: Sorted so colliders are always inserted in the same order, which keeps rapier deterministic
+		let mut indices: Vec<(i32, i32)> = chunks.keys().copied().collect();
+		indices.sort();
+
+		for index in &indices {
+			let chunk = chunks.get_mut(index).unwrap();
 			if chunk.colliders_dirty {
-				if let Some(collider_handles) = self.chunk_colliders.get(index) {
-					for i in 0..collider_handles.len() {
-						self.collider_set.remove(collider_handles[i], &mut self.island_manager, &mut self.rigid_body_set, false);
-					}
-				}
+				self.remove_chunk_colliders(*index);
 
 				let mut collider_handles = vec![];
 				for edge in &chunk.edges {
@@ -150,23 +203,9 @@ impl RapierHandler {
 			}
 		}
 	}
-
-	pub fn debug_render(&self, render_draw: &mut Draw, debug_info: &DebugInfo) {
-		for i in 0..self.ball_body_handles.len() {
-			let ball_body = &self.rigid_body_set[self.ball_body_handles[i]];
-			let pos = ball_body.translation();
-			render_draw.circle(8.).translate(pos.x * PHYS_SCALE, pos.y * PHYS_SCALE);
-		}
-
-		if debug_info.debug_rs_body_edges {
-			for i in 0..self.rigid_sand_bodies.len() {
-				self.rigid_sand_bodies[i].debug_render(render_draw, &self.rigid_body_set);
-			}
-		}
-	}
 }
 
 #[derive(Debug, PartialEq)]
-pub enum SelectBody {
-    Ball, SandBodyBall, SandBodySquare, SandBodyRectangle
+pub enum SelectBody {
+    Ball, SandBodyBall, SandBodySquare, SandBodyRectangle
 }
@@ -1,5 +1,5 @@
 use contour::ContourBuilder;
-use notan::{draw::{Draw, DrawShapes}, prelude::Color, math::{Mat3, Vec2}};
+use glam::Vec2;
 use rapier2d::{prelude::{RigidBodyHandle, RigidBodyBuilder, ColliderBuilder, RigidBodySet, ColliderSet, nalgebra}, na::vector, parry::transformation::vhacd::VHACDParameters};
 use simplify_polyline::*;
 
@@ -40,10 +40,10 @@ impl RigidSandBody {
 					body_elements.push(row);
 				}
 		
-				for i in 0..100 {
-					for j in 0..100 {
+				for (i, row) in body_elements.iter_mut().enumerate() {
+					for (j, cell) in row.iter_mut().enumerate() {
 						if Vec2::new(i as f32, j as f32).distance(Vec2::new(50., 50.)) > 50. {
-							body_elements[i][j] = None;
+							*cell = None;
 						}
 					}
 				}
@@ -78,8 +78,12 @@ impl RigidSandBody {
 			},
 		}
 
+		Self::from_elements(x, y, body_elements, rigid_body_set, collider_set)
+	}
+
+	// INFO: Builds the body around already existing elements, e.g. the ones read from a save file
+	pub fn from_elements(x: f32, y: f32, body_elements: Vec<Vec<Option<Cell>>>, rigid_body_set: &mut RigidBodySet, collider_set: &mut ColliderSet) -> Self {
 		let (rigid_body_handle, final_edge) = create_rigid_body_handle(x, y, &body_elements, rigid_body_set, collider_set);
-		
 
 		Self {
 			body_elements,
@@ -89,39 +93,17 @@ impl RigidSandBody {
 		}
 	}
 
-	pub fn debug_render(&self, draw: &mut Draw, rigid_body_set: &RigidBodySet) {
-		let body = &rigid_body_set[self.rigid_body_handle];
-
-		let translation = Mat3::from_translation(Vec2::new(body.translation().x * PHYS_SCALE, body.translation().y * PHYS_SCALE));
-		let rotation = Mat3::from_angle(body.rotation().angle());
-		let matrix = translation * rotation;
-
-		draw.transform().push(matrix);
-		
-	    let mut prev_point = &self.body_edge[0];
-	    for point in &self.body_edge {
-	        draw.line(
-	            ((prev_point.x) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR,
-	             (prev_point.y) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR),
-	            ((point.x) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR,
-	             (point.y) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR)
-	        ).color(Color::MAGENTA);
-	        prev_point = point;
-	    }
-		draw.transform().pop();
-	}
-
 	pub fn remove_from_rapier(&mut self, ) {
 		
 	}
 }
 
 // INFO: Create a body map for the tracing algorithm to use
-pub fn gen_body_map(body_elements: &Vec<Vec<Option<Cell>>>) -> Vec<f64> {
+pub fn gen_body_map(body_elements: &[Vec<Option<Cell>>]) -> Vec<f64> {
 	let mut map = vec![];
 	for i in 0..body_elements[0].len() {
-		for j in 0..body_elements.len() {
-			if body_elements[j][i].is_some() {
+		for row in body_elements {
+			if row[i].is_some() {
 				map.push(1.);
 			} else {
 				map.push(0.);
@@ -136,7 +118,7 @@ pub fn gen_body_map(body_elements: &Vec<Vec<Option<Cell>>>) -> Vec<f64> {
 	We use the "contour" crate for getting all edges from a body map, that includes exteriors and interiors
 	Then we simplify the edges with the "simplify-polyline" crate
 */
-pub fn get_edge_from_body_map(body_map: Vec<f64>, body_elements: &Vec<Vec<Option<Cell>>>) -> Vec<Vec<nalgebra::OPoint<f32, nalgebra::Const<2>>>> {
+pub fn get_edge_from_body_map(body_map: Vec<f64>, body_elements: &[Vec<Option<Cell>>]) -> Vec<Vec<nalgebra::OPoint<f32, nalgebra::Const<2>>>> {
 	// INFO: Get all unsimplified edges from the body map
 	let c = ContourBuilder::new(body_elements.len(), body_elements[0].len(), false);
 	let edges = c.contours(&body_map, &[0.5]).unwrap();
@@ -176,23 +158,25 @@ pub fn get_edge_from_body_map(body_map: Vec<f64>, body_elements: &Vec<Vec<Option
 // INFO: Here we place the rigid sand body into the rapier world
 fn create_rigid_body_handle(
 		x: f32, y: f32,
-		body_elements: &Vec<Vec<Option<Cell>>>, 
+		body_elements: &[Vec<Option<Cell>>], 
 		rigid_body_set: &mut RigidBodySet,
 		collider_set: &mut ColliderSet) -> (RigidBodyHandle, RSBodyEdge)
 {
 	// INFO: Create the body map
-	let body_map = gen_body_map(&body_elements);
+	let body_map = gen_body_map(body_elements);
 
 	// INFO: Get edges from the body map
-	let final_edge = get_edge_from_body_map(body_map, &body_elements)[0].to_owned();
+	let final_edge = get_edge_from_body_map(body_map, body_elements)[0].to_owned();
 	
 	let rigid_body = RigidBodyBuilder::dynamic().translation(vector![x, y]).build();
 
 	let indices: Vec<[u32; 2]> = (0..final_edge.len() - 1).map(|i| [i as u32, i as u32 + 1]).collect();
 
 	// INFO: Here we set the accuracy of the generated shape
-	let mut params = VHACDParameters::default();
-	params.concavity = 0.01;
+	let params = VHACDParameters {
+		concavity: 0.01,
+		..Default::default()
+	};
 	
 	let collider = ColliderBuilder::convex_decomposition_with_params(&final_edge, &indices, &params).build();
 
@@ -0,0 +1,100 @@
+use super::{chunk_manager::ChunkManager, element::*};
+
+// INFO: Built-in worlds so the simulation can be run without anyone drawing into it (CLI, CI, bug repros)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scenario {
+    Empty,
+    SandPile,
+    WaterTank,
+    Bonfire,
+    Avalanche,
+    LavaFlow,
+    Vessels,
+}
+
+impl Scenario {
+    pub const ALL: [Scenario; 7] = [
+        Scenario::Empty, Scenario::SandPile, Scenario::WaterTank, Scenario::Bonfire, Scenario::Avalanche, Scenario::LavaFlow, Scenario::Vessels
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scenario::Empty => "empty",
+            Scenario::SandPile => "sand_pile",
+            Scenario::WaterTank => "water_tank",
+            Scenario::Bonfire => "bonfire",
+            Scenario::Avalanche => "avalanche",
+            Scenario::LavaFlow => "lava_flow",
+            Scenario::Vessels => "vessels",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Scenario> {
+        Scenario::ALL.into_iter().find(|scenario| scenario.name() == name)
+    }
+
+    pub fn build(&self, chunk_manager: &mut ChunkManager) {
+        let (min, max) = chunk_manager.world_cell_bounds();
+        let center_x = (min.0 + max.0) / 2;
+
+        if *self != Scenario::Empty {
+            // INFO: Every scenario stands on a floor at the bottom of the starting chunks
+            chunk_manager.fill_cells((min.0, max.1 - 10), max, &solid_element());
+        }
+
+        match self {
+            Scenario::Empty => (),
+            Scenario::SandPile => {
+                chunk_manager.fill_cells((center_x - 40, max.1 - 250), (center_x + 40, max.1 - 170), &sand_element());
+                chunk_manager.fill_cells((center_x + 120, max.1 - 200), (center_x + 160, max.1 - 140), &gravel_element());
+                chunk_manager.fill_cells((center_x - 160, max.1 - 200), (center_x - 120, max.1 - 140), &dirt_element());
+            }
+            Scenario::WaterTank => {
+                chunk_manager.fill_cells((center_x - 150, max.1 - 110), (center_x - 141, max.1 - 11), &brick_element());
+                chunk_manager.fill_cells((center_x + 141, max.1 - 110), (center_x + 150, max.1 - 11), &brick_element());
+                chunk_manager.fill_cells((center_x - 140, max.1 - 90), (center_x + 140, max.1 - 11), &water_element());
+                chunk_manager.fill_cells((center_x - 20, max.1 - 220), (center_x + 20, max.1 - 150), &sand_element());
+            }
+            Scenario::Bonfire => {
+                chunk_manager.fill_cells((center_x - 60, max.1 - 40), (center_x + 60, max.1 - 11), &wood_element());
+                chunk_manager.fill_cells((center_x - 30, max.1 - 60), (center_x + 30, max.1 - 41), &sawdust_element());
+                chunk_manager.fill_cells((center_x + 100, max.1 - 30), (center_x + 160, max.1 - 11), &coal_element());
+
+                let mut fire = fire_element();
+                fire.lifetime = 150;
+                chunk_manager.fill_cells((center_x - 10, max.1 - 70), (center_x + 10, max.1 - 61), &fire);
+            }
+            // INFO: Falls through every chunk of the world, useful for checking cross chunk movement
+            Scenario::Avalanche => {
+                chunk_manager.fill_cells((min.0, min.1 + 20), (max.0, min.1 + 60), &sand_element());
+                chunk_manager.fill_cells((min.0, min.1 + 100), (max.0, min.1 + 120), &water_element());
+                chunk_manager.fill_cells((min.0 + 200, min.1 + 300), (max.0 - 200, min.1 + 310), &gravel_element());
+            }
+            // INFO: Lava running into sand, water and ice, for checking heat and phase changes
+            Scenario::LavaFlow => {
+                chunk_manager.fill_cells((center_x - 250, max.1 - 40), (center_x - 50, max.1 - 11), &sand_element());
+                chunk_manager.fill_cells((center_x - 180, max.1 - 200), (center_x - 120, max.1 - 150), &lava_element());
+
+                chunk_manager.fill_cells((center_x + 50, max.1 - 80), (center_x + 59, max.1 - 11), &brick_element());
+                chunk_manager.fill_cells((center_x + 241, max.1 - 80), (center_x + 250, max.1 - 11), &brick_element());
+                chunk_manager.fill_cells((center_x + 60, max.1 - 60), (center_x + 240, max.1 - 11), &water_element());
+                chunk_manager.fill_cells((center_x + 200, max.1 - 70), (center_x + 230, max.1 - 40), &ice_element());
+                chunk_manager.fill_cells((center_x + 80, max.1 - 200), (center_x + 140, max.1 - 150), &lava_element());
+            }
+            // INFO: A U-tube with water in one arm only, the water has to rise in the other arm until both are level
+            Scenario::Vessels => {
+                chunk_manager.fill_cells((center_x - 150, max.1 - 250), (center_x - 141, max.1 - 11), &brick_element());
+                chunk_manager.fill_cells((center_x - 100, max.1 - 250), (center_x + 100, max.1 - 31), &brick_element());
+                chunk_manager.fill_cells((center_x + 141, max.1 - 250), (center_x + 150, max.1 - 11), &brick_element());
+                chunk_manager.fill_cells((center_x - 140, max.1 - 200), (center_x - 101, max.1 - 11), &water_element());
+                chunk_manager.fill_cells((center_x - 100, max.1 - 30), (center_x + 100, max.1 - 11), &water_element());
+            }
+        }
+
+        if *self != Scenario::Empty {
+            // INFO: The world has no edges anymore, walls keep everything from flowing off the floor
+            chunk_manager.fill_cells(min, (min.0 + 9, max.1), &solid_element());
+            chunk_manager.fill_cells((max.0 - 9, min.1), max, &solid_element());
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+use glam::Vec2;
+
+use super::{
+    chunk::{self, Chunk, COLS, ROWS, UPSCALE_FACTOR},
+    chunk_manager::WorldChunks,
+    element::{air_element, el_from_enum, Cell, ElColliderType, State},
+    element_registry::registry,
+    element_texture_handler::ElementTexHandler,
+    rapier_edge_gen::edges_from_chunk,
+    rapier_world_handler::PHYS_SCALE,
+};
+
+// INFO: How many structure cells are searched through every frame, a search through a bigger structure carries on next frame
+const SEARCH_BUDGET: usize = 32768;
+
+// INFO: Unsupported structures need at least this many cells and to be this wide and high to become a rigid body, smaller ones fall apart
+const MIN_BODY_CELLS: usize = 16;
+const MIN_BODY_SIZE: i32 = 4;
+
+/*
+    INFO: Structural cells that touch each other make up a structure. A structure holds as long as one of its cells
+    is an `anchor` (the Solid walls), rests on something that does not fall or reaches down into a chunk that is not loaded (the bottom of the world).
+    A structure that goes on sideways into a chunk that is not loaded is left alone, it is searched again once that chunk is loaded.
+    Only the chunks whose colliders changed are searched, that is where a structure can have lost its support. They are searched in rounds,
+    every round goes through the chunks that changed during the last one, so a search can take a few frames.
+    It is part of the world state and saved with it, so a loaded world goes on to find the same structures
+*/
+#[derive(Default)]
+pub struct SupportCheck {
+    // INFO: Chunks whose colliders changed, they are searched in the next round
+    pub next_round: BTreeSet<(i32, i32)>,
+    // INFO: Chunks left in this round and the cell of the first one the scan for structures is at
+    pub round: VecDeque<(i32, i32)>,
+    pub cursor: usize,
+    // INFO: Cells of the structures this round already went through
+    pub checked: HashSet<(i32, i32)>,
+    pub search: Option<Search>,
+}
+
+// INFO: A flood fill through one structure, the cells before `next` were already looked at
+pub struct Search {
+    pub structure: Vec<(i32, i32)>,
+    pub next: usize,
+    visited: HashSet<(i32, i32)>,
+}
+
+impl Search {
+    pub fn new(structure: Vec<(i32, i32)>, next: usize) -> Self {
+        let visited = structure.iter().copied().collect();
+        Self { structure, next, visited }
+    }
+
+    /*
+        INFO: Looks at the next cells of the structure until it runs out of budget (returns None) or knows whether it falls.
+        Reaching a cell that was checked this round means the structure was already found to be supported,
+        a structure without support would have been searched through completely
+    */
+    fn carry_on(&mut self, chunks: &WorldChunks, checked: &HashSet<(i32, i32)>, budget: &mut usize) -> Option<Outcome> {
+        while self.next < self.structure.len() {
+            if *budget == 0 {
+                return None;
+            }
+            *budget -= 1;
+
+            let (x, y) = self.structure[self.next];
+            self.next += 1;
+
+            // INFO: Changed since it was found, whether the rest still falls is made sure of at the end
+            let Some(cell) = cell_at(chunks, (x, y)).filter(|cell| is_structure(cell)) else {
+                continue;
+            };
+            if registry().get(cell.element).anchor {
+                return Some(Outcome::Held);
+            }
+            match cell_at(chunks, (x, y + 1)) {
+                None => return Some(Outcome::Held),
+                Some(below) if !is_structure(below) && holds_up(below) => return Some(Outcome::RestsOn((x, y))),
+                _ => (),
+            }
+
+            for next in [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)] {
+                // INFO: Goes on into a chunk that is not loaded, it is searched again when the chunk is loaded
+                let Some(other) = cell_at(chunks, next) else {
+                    return Some(Outcome::Held);
+                };
+                if !is_structure(other) || self.visited.contains(&next) {
+                    continue;
+                }
+                if checked.contains(&next) {
+                    return Some(Outcome::Held);
+                }
+                self.visited.insert(next);
+                self.structure.push(next);
+            }
+        }
+        Some(Outcome::Falls)
+    }
+
+    // INFO: A search that took a few frames could be out of date, it only falls if it is still the whole structure and nothing holds it up
+    fn still_falls(&self, chunks: &WorldChunks) -> bool {
+        self.structure.iter().all(|&(x, y)| {
+            let Some(cell) = cell_at(chunks, (x, y)).filter(|cell| is_structure(cell)) else {
+                return false;
+            };
+            !registry().get(cell.element).anchor
+                && cell_at(chunks, (x, y + 1)).is_some_and(|below| is_structure(below) || !holds_up(below))
+                && [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)]
+                    .into_iter()
+                    .all(|next| cell_at(chunks, next).is_some_and(|other| !is_structure(other) || self.visited.contains(&next)))
+        })
+    }
+}
+
+enum Outcome {
+    Held,
+    // INFO: The cell rests on something that can move away without any chunk colliders changing (powders, liquids and rigid sand bodies)
+    RestsOn((i32, i32)),
+    Falls,
+}
+
+impl SupportCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // INFO: Carries on searching the chunks whose colliders changed and returns the world cells of every structure that lost its support
+    pub fn unsupported_structures(&mut self, chunks: &WorldChunks) -> Vec<Vec<(i32, i32)>> {
+        self.next_round.extend(chunks.iter().filter(|(_, chunk)| chunk.colliders_dirty).map(|(index, _)| *index));
+        // INFO: At most one round starts every frame, structures that rest on powders are searched again every round
+        if self.round.is_empty() && self.search.is_none() {
+            self.round = std::mem::take(&mut self.next_round).into_iter().collect();
+            self.cursor = 0;
+            self.checked.clear();
+        }
+
+        let mut structures = vec![];
+        let mut budget = SEARCH_BUDGET;
+        while budget > 0 {
+            if self.search.is_none() {
+                let Some(start) = self.next_start(chunks) else {
+                    break;
+                };
+                self.search = Some(Search::new(vec![start], 0));
+            }
+
+            let Some(outcome) = self.search.as_mut().unwrap().carry_on(chunks, &self.checked, &mut budget) else {
+                break;
+            };
+            let search = self.search.take().unwrap();
+            match outcome {
+                Outcome::Held => (),
+                // INFO: Nothing tells when the cell below moves away, so the structure is searched again next round
+                Outcome::RestsOn((x, y)) => {
+                    self.next_round.insert((x.div_euclid(COLS as i32), y.div_euclid(ROWS as i32)));
+                }
+                Outcome::Falls => {
+                    if search.still_falls(chunks) {
+                        structures.push(search.structure.clone());
+                    }
+                }
+            }
+            self.checked.extend(search.structure);
+        }
+
+        structures
+    }
+
+    // INFO: The next structural cell in this round's chunks that was not searched yet
+    fn next_start(&mut self, chunks: &WorldChunks) -> Option<(i32, i32)> {
+        while let Some(&index) = self.round.front() {
+            if let Some(chunk) = chunks.get(&index) {
+                while self.cursor < COLS * ROWS {
+                    let (i, j) = (self.cursor / ROWS, self.cursor % ROWS);
+                    self.cursor += 1;
+
+                    let start = (index.0 * COLS as i32 + i as i32, index.1 * ROWS as i32 + j as i32);
+                    let cell = &chunk.grid[i][j];
+                    // INFO: Most structure cells are walls and floors, which hold up themselves
+                    if is_structure(cell) && !registry().get(cell.element).anchor && !self.checked.contains(&start) {
+                        return Some(start);
+                    }
+                }
+            }
+
+            self.round.pop_front();
+            self.cursor = 0;
+        }
+        None
+    }
+}
+
+// INFO: Structures fall as a whole rigid body however big they are, only thin or small ones would not make a proper collider
+pub fn becomes_body(structure: &[(i32, i32)]) -> bool {
+    let (min, max) = bounds(structure);
+    structure.len() >= MIN_BODY_CELLS && max.0 - min.0 + 1 >= MIN_BODY_SIZE && max.1 - min.1 + 1 >= MIN_BODY_SIZE
+}
+
+// INFO: The cells of the structure fall apart into their `collapses_into` element (solid dirt into dirt, brick into gravel), the others stay
+pub fn collapse(chunks: &mut WorldChunks, structure: &[(i32, i32)], tex_handler: &ElementTexHandler) {
+    for &(x, y) in structure {
+        let (chunk, i, j) = chunk_cell(chunks, (x, y));
+        let old = chunk.grid[i][j];
+        if let Some(into) = registry().get(old.element).collapses_into {
+            let mut cell = el_from_enum(into);
+            cell.temperature = old.temperature;
+            chunk::modify_chunk_element(chunk, i as i32, j as i32, &cell, tex_handler, false);
+        }
+    }
+    rebuild_edges(chunks, structure);
+}
+
+/*
+    INFO: Takes the cells of the structure out of the chunks, with their elements and colors, as the elements of a rigid sand body.
+    Returns the elements and the world position of their top left corner in physics units
+*/
+pub fn lift_out(chunks: &mut WorldChunks, structure: &[(i32, i32)]) -> (Vec<Vec<Option<Cell>>>, Vec2) {
+    let (min, max) = bounds(structure);
+    let mut body_elements = vec![vec![None; (max.1 - min.1 + 1) as usize]; (max.0 - min.0 + 1) as usize];
+
+    for &(x, y) in structure {
+        let (chunk, i, j) = chunk_cell(chunks, (x, y));
+        let mut cell = chunk.grid[i][j];
+        cell.collider_type = ElColliderType::Body;
+        body_elements[(x - min.0) as usize][(y - min.1) as usize] = Some(cell);
+
+        chunk.grid[i][j] = air_element();
+        chunk::update_byte(&mut chunk.bytes, i, j, &chunk.grid[i][j].color);
+        chunk.dirty_tex = true;
+    }
+    rebuild_edges(chunks, structure);
+
+    // INFO: A quarter cell in, so rounding puts the elements back onto the same cells while the body has not moved yet
+    let position = (Vec2::new(min.0 as f32, min.1 as f32) + 0.25) * UPSCALE_FACTOR / PHYS_SCALE;
+    (body_elements, position)
+}
+
+fn bounds(structure: &[(i32, i32)]) -> ((i32, i32), (i32, i32)) {
+    structure.iter().fold(((i32::MAX, i32::MAX), (i32::MIN, i32::MIN)), |(min, max), &(x, y)| {
+        ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+    })
+}
+
+// INFO: The chunk colliders were already rebuilt during the update, the chunks the structure was in need new ones
+fn rebuild_edges(chunks: &mut WorldChunks, structure: &[(i32, i32)]) {
+    let mut indices: Vec<(i32, i32)> = structure.iter().map(|&(x, y)| (x.div_euclid(COLS as i32), y.div_euclid(ROWS as i32))).collect();
+    indices.sort();
+    indices.dedup();
+    for index in indices {
+        let chunk = chunks.get_mut(&index).unwrap();
+        chunk.colliders_dirty = true;
+        edges_from_chunk(chunk);
+    }
+}
+
+fn chunk_cell(chunks: &mut WorldChunks, (x, y): (i32, i32)) -> (&mut Chunk, usize, usize) {
+    let chunk = chunks.get_mut(&(x.div_euclid(COLS as i32), y.div_euclid(ROWS as i32))).unwrap();
+    (chunk, x.rem_euclid(COLS as i32) as usize, y.rem_euclid(ROWS as i32) as usize)
+}
+
+// INFO: Rigid sand body cells are only in the chunks during the update, they have a body collider and are not part of the terrain
+fn is_structure(cell: &Cell) -> bool {
+    cell.collider_type == ElColliderType::Chunk && registry().get(cell.element).structural
+}
+
+// INFO: Everything that does not fall holds up what rests on it: machines and other solids, landed powders, liquids and rigid sand bodies
+fn holds_up(cell: &Cell) -> bool {
+    if cell.collider_type == ElColliderType::Body {
+        return true;
+    }
+    match cell.state {
+        State::Powder => !cell.free_falling,
+        State::Gas | State::Plasma => false,
+        _ => true,
+    }
+}
+
+fn cell_at(chunks: &WorldChunks, (x, y): (i32, i32)) -> Option<&Cell> {
+    let chunk = chunks.get(&(x.div_euclid(COLS as i32), y.div_euclid(ROWS as i32)))?;
+    Some(&chunk.grid[x.rem_euclid(COLS as i32) as usize][y.rem_euclid(ROWS as i32) as usize])
+}
@@ -0,0 +1,114 @@
+use fastrand::Rng;
+use glam::Vec2;
+
+use super::{
+    chunk::{self, COLS},
+    chunk_manager::ChunkManager,
+    element::{el_from_enum, Element},
+};
+
+// INFO: Strongest wind the weather picks by itself when it changes
+const MAX_WIND: f32 = 3.;
+// INFO: Mixed into the world seed so the weather does not roll the same numbers as the chunks
+const WEATHER_SEED: u64 = 0x5EA7_4E12;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Precipitation {
+    Clear,
+    Rain,
+    Snow,
+}
+
+impl Precipitation {
+    pub const ALL: [Precipitation; 3] = [Precipitation::Clear, Precipitation::Rain, Precipitation::Snow];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Precipitation::Clear => "clear",
+            Precipitation::Rain => "rain",
+            Precipitation::Snow => "snow",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|precipitation| precipitation.name() == name)
+    }
+
+    fn element(&self) -> Option<Element> {
+        match self {
+            Precipitation::Clear => None,
+            Precipitation::Rain => Some(Element::Water),
+            Precipitation::Snow => Some(Element::Snow),
+        }
+    }
+}
+
+/*
+    INFO: Rain or snow falls from the top row of the highest chunks the view reaches. Not from the highest loaded chunks,
+    the chunks above active chunks get loaded too, so the top of the loaded world would climb up every frame it rains.
+    The wind is handed to the chunk manager and pushes everything in the air (see base_movement::blow).
+    With `changes` on the weather picks a new precipitation and wind every `change_interval` frames.
+    Everything random comes from the world seed and the frame, so the same seed still gives the same world
+*/
+pub struct Weather {
+    pub precipitation: Precipitation,
+    // INFO: Chance per frame that a cell of the top row starts raining or snowing
+    pub rate: f32,
+    pub wind: Vec2,
+    pub changes: bool,
+    pub change_interval: u32,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Weather {
+    pub fn new() -> Self {
+        Self {
+            precipitation: Precipitation::Clear,
+            rate: 0.01,
+            wind: Vec2::ZERO,
+            changes: false,
+            change_interval: 3000,
+        }
+    }
+
+    pub fn update(&mut self, chunk_manager: &mut ChunkManager) {
+        let frame = chunk_manager.chunk_frame_count as u64;
+        let mut rng = Rng::with_seed((chunk_manager.seed ^ WEATHER_SEED).wrapping_add(frame));
+
+        if self.changes && self.change_interval > 0 && frame.is_multiple_of(self.change_interval as u64) {
+            self.precipitation = Precipitation::ALL[rng.usize(..Precipitation::ALL.len())];
+            self.wind = Vec2::new((rng.f32() * 2. - 1.) * MAX_WIND, 0.);
+        }
+
+        chunk_manager.wind = self.wind;
+
+        let (Some(element), Some((min, max))) = (self.precipitation.element(), chunk_manager.view) else {
+            return;
+        };
+        if self.rate <= 0. {
+            return;
+        }
+
+        let (min, max) = (chunk::world_to_chunk_index(min), chunk::world_to_chunk_index(max));
+        for x in min.0..=max.0 {
+            let Some(chunk) = chunk_manager.chunks.get_mut(&(x, min.1)) else {
+                continue;
+            };
+
+            for i in 0..COLS {
+                if rng.f32() >= self.rate || chunk.grid[i][0].element != Element::Air {
+                    continue;
+                }
+
+                chunk.grid[i][0] = el_from_enum(element);
+                chunk::update_byte(&mut chunk.bytes, i, 0, &chunk.grid[i][0].color);
+                chunk::wake_up(chunk, i, 0);
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use super::{
+    base_movement::{get, set},
+    chunk::{Grid, MovData},
+    element::{air_element, el_from_enum, steam_element, Element, State},
+    element_registry::registry,
+};
+
+// INFO: Chance per frame that a powder soaks up a water cell next to it, so water seeps in over a few frames
+const ABSORB_CHANCE: f32 = 0.05;
+// INFO: Chances per frame that a wet powder next to a gas loses one water cell, slowly in the open and fast when it is hot
+const DRY_CHANCE: f32 = 0.0005;
+const HOT_DRY_CHANCE: f32 = 0.05;
+// INFO: Wet powders this hot or next to fire or lava dry fast and let the water out as steam
+const HOT: f32 = 100.;
+// INFO: Every water cell a powder soaked up makes it this much darker
+const DARKEN: (u16, u16) = (3, 4);
+
+/*
+    INFO: Powders that absorb water soak up the water cells next to them one by one, the wetness is how many they hold,
+    so no water appears or disappears, and pass them on to drier powders. Every water cell makes them darker and stickier (see base_movement::downward_sides),
+    at their `soaked` wetness they turn into another element (sand into wet sand, dirt into mud) that keeps the water.
+    Next to a gas they slowly dry out again, near fire or lava or when hot they dry fast and steam,
+    at their `dried` wetness they turn back into another element (wet sand into sand, mud into dirt).
+    Returns true if the cell turned into another element
+*/
+pub fn handle_wetness(future_grid: &mut Grid, i: usize, j: usize, mov_dt: &mut MovData) -> bool {
+    let cell = future_grid[i][j];
+    let def = registry().get(cell.element);
+    if def.absorbs == 0 && cell.wetness == 0 {
+        return false;
+    }
+
+    let (x, y) = (i as i32, j as i32);
+    let neighbors = [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)];
+    let start = mov_dt.rng.usize(..neighbors.len());
+
+    if cell.wetness < def.absorbs && mov_dt.rng.f32() < ABSORB_CHANCE {
+        for k in 0..neighbors.len() {
+            let (nx, ny) = neighbors[(start + k) % neighbors.len()];
+            if get(nx, ny, future_grid, mov_dt).element == Element::Water {
+                set(nx, ny, future_grid, mov_dt, air_element());
+                return set_wetness(x, y, future_grid, mov_dt, cell.wetness + 1);
+            }
+        }
+    }
+
+    if cell.wetness == 0 {
+        return false;
+    }
+
+    // INFO: Water seeps from wetter powders into drier ones next to them, so it soaks into the ground and not only its surface
+    let (nx, ny) = neighbors[start];
+    let other = get(nx, ny, future_grid, mov_dt);
+    if other.wetness + 1 < cell.wetness && other.wetness < registry().get(other.element).absorbs && mov_dt.rng.f32() < ABSORB_CHANCE {
+        set_wetness(nx, ny, future_grid, mov_dt, other.wetness + 1);
+        return set_wetness(x, y, future_grid, mov_dt, cell.wetness - 1);
+    }
+
+    let mut hot = cell.temperature >= HOT;
+    let mut gas = None;
+    for k in 0..neighbors.len() {
+        let (nx, ny) = neighbors[(start + k) % neighbors.len()];
+        let other = get(nx, ny, future_grid, mov_dt);
+        hot |= other.element == Element::Fire || other.element == Element::Lava;
+        if gas.is_none() && other.state == State::Gas {
+            gas = Some((nx, ny, other.element));
+        }
+    }
+    let Some((gx, gy, gas)) = gas else {
+        return false;
+    };
+
+    *mov_dt.keep_active = true;
+    mov_dt.dirty_rect.set_temp(i, j);
+
+    if mov_dt.rng.f32() >= if hot { HOT_DRY_CHANCE } else { DRY_CHANCE } {
+        return false;
+    }
+    if hot && gas == Element::Air {
+        set(gx, gy, future_grid, mov_dt, steam_element());
+    }
+    set_wetness(x, y, future_grid, mov_dt, cell.wetness - 1)
+}
+
+fn set_wetness(x: i32, y: i32, future_grid: &mut Grid, mov_dt: &mut MovData, wetness: u8) -> bool {
+    let mut cell = get(x, y, future_grid, mov_dt);
+    let def = registry().get(cell.element);
+
+    let into = match (def.soaked, def.dried) {
+        (Some((soaked, into)), _) if wetness >= soaked => Some(into),
+        (_, Some((dried, into))) if wetness <= dried => Some(into),
+        _ => None,
+    };
+
+    // INFO: The color of an element is the one at the wetness it is placed with
+    let from = match into {
+        Some(into) => {
+            let temperature = cell.temperature;
+            cell = el_from_enum(into);
+            cell.temperature = temperature;
+            cell.wetness
+        }
+        None => cell.wetness,
+    };
+    for _ in wetness..from {
+        cell.color = lighten(cell.color);
+    }
+    for _ in from..wetness {
+        cell.color = darken(cell.color);
+    }
+    cell.wetness = wetness;
+
+    set(x, y, future_grid, mov_dt, cell);
+    into.is_some()
+}
+
+fn darken(mut color: [u8; 4]) -> [u8; 4] {
+    for c in &mut color[..3] {
+        *c = (*c as u16 * DARKEN.0 / DARKEN.1) as u8;
+    }
+    color
+}
+
+fn lighten(mut color: [u8; 4]) -> [u8; 4] {
+    for c in &mut color[..3] {
+        *c = (*c as u16 * DARKEN.1 / DARKEN.0).min(255) as u8;
+    }
+    color
+}
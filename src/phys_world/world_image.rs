@@ -0,0 +1,30 @@
+use image::RgbaImage;
+
+use super::{chunk::{COLS, ROWS}, chunk_manager::WorldChunks};
+
+// INFO: Stitches the bytes of the chunks in the given range (inclusive) into one image, places without a loaded chunk stay transparent
+pub fn world_image(chunks: &WorldChunks, min: (i32, i32), max: (i32, i32)) -> RgbaImage {
+    if min.0 > max.0 || min.1 > max.1 {
+        return RgbaImage::new(0, 0);
+    }
+
+    let width = (max.0 - min.0 + 1) as usize * COLS;
+    let height = (max.1 - min.1 + 1) as usize * ROWS;
+    let mut bytes = vec![0; width * height * 4];
+
+    for (index, chunk) in chunks.iter() {
+        if index.0 < min.0 || index.1 < min.1 || index.0 > max.0 || index.1 > max.1 {
+            continue;
+        }
+
+        let offset_x = (index.0 - min.0) as usize * COLS;
+        let offset_y = (index.1 - min.1) as usize * ROWS;
+
+        for j in 0..ROWS {
+            let start = ((offset_y + j) * width + offset_x) * 4;
+            bytes[start..start + COLS * 4].copy_from_slice(&chunk.bytes[j * COLS * 4..(j + 1) * COLS * 4]);
+        }
+    }
+
+    RgbaImage::from_raw(width as u32, height as u32, bytes).unwrap()
+}
@@ -0,0 +1,740 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use glam::Vec2;
+use rapier2d::prelude::{nalgebra, vector, Rotation};
+
+use super::{
+    all_physics_manager::PhysicsManager,
+    chunk::{self, Chunk, COLS, ROWS},
+    element::*,
+    element_registry::{registry, ElementRegistry},
+    rapier_edge_gen::edges_from_chunk,
+    rigid_sand_body::RigidSandBody,
+    support::{Search, SupportCheck},
+};
+
+/*
+    INFO: Layout of a save file, everything is little endian
+    header: magic, version, seed, frame count (version 1 also had the chunk ranges), since version 11 the world bounds
+    cells: element, action, velocity, lifetime, color, collider type, since version 3 the temperature, since version 5 the charge, since version 7 the wetness and since version 8 whether it is free falling
+    element table: the names of the elements the cells refer to, so adding or reordering elements does not break old saves
+    chunks: index, activity and dirty rect, since version 4 the pressure field, since version 10 whether a liquid moved in it,
+    then all cells run length encoded. Chunks that were unloaded to disk are included
+    rigid sand bodies: position, rotation, velocities and their elements
+    support check (since version 9): the chunks of the next and the current round, the scan cursor, the checked cells and the unfinished search
+*/
+const SAVE_MAGIC: &[u8; 4] = b"FSSW";
+pub const SAVE_VERSION: u32 = 11;
+
+// INFO: Unloaded chunks are stored in their own small files: magic, version, element table and the chunk
+const CHUNK_MAGIC: &[u8; 4] = b"FSSC";
+
+// INFO: Element names are short, a longer length means the file is broken and is not allocated
+const MAX_NAME_LEN: usize = 256;
+// INFO: Cells refer to their element by a u16 index into the element table
+const MAX_ELEMENTS: usize = u16::MAX as usize + 1;
+
+pub fn save_world_to_file(physics_manager: &PhysicsManager, path: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    save_world(physics_manager, &mut writer)?;
+    writer.flush()
+}
+
+pub fn load_world_from_file(physics_manager: &mut PhysicsManager, path: &Path) -> io::Result<()> {
+    load_world(physics_manager, &mut BufReader::new(File::open(path)?))
+}
+
+pub fn save_world(physics_manager: &PhysicsManager, writer: &mut impl Write) -> io::Result<()> {
+    let chunk_manager = &physics_manager.chunk_manager;
+    let rapier_handler = &physics_manager.rapier_handler;
+
+    writer.write_all(SAVE_MAGIC)?;
+    write_u32(writer, SAVE_VERSION)?;
+    write_u64(writer, chunk_manager.seed)?;
+    write_u64(writer, chunk_manager.chunk_frame_count as u64)?;
+    match chunk_manager.world_bounds {
+        Some((min, max)) => {
+            writer.write_all(&[1])?;
+            for value in [min.0, min.1, max.0, max.1] {
+                write_i32(writer, value)?;
+            }
+        }
+        None => writer.write_all(&[0])?,
+    }
+
+    write_element_table(writer, registry())?;
+
+    // INFO: Sorted so the same world always gives the same file
+    let mut indices: Vec<(i32, i32)> = chunk_manager.chunk_index.iter().chain(chunk_manager.stored_chunks.iter().copied()).collect();
+    indices.sort();
+
+    write_u32(writer, indices.len() as u32)?;
+    for index in indices {
+        match chunk_manager.chunks.get(&index) {
+            Some(chunk) => write_chunk(writer, chunk)?,
+            None => write_chunk(writer, &chunk_manager.read_stored_chunk(index)?)?,
+        }
+    }
+
+    write_u32(writer, rapier_handler.rigid_sand_bodies.len() as u32)?;
+    for rsbody in &rapier_handler.rigid_sand_bodies {
+        let body = &rapier_handler.rigid_body_set[rsbody.rigid_body_handle];
+        write_f32(writer, body.translation().x)?;
+        write_f32(writer, body.translation().y)?;
+        write_f32(writer, body.rotation().angle())?;
+        write_f32(writer, body.linvel().x)?;
+        write_f32(writer, body.linvel().y)?;
+        write_f32(writer, body.angvel())?;
+
+        write_u32(writer, rsbody.body_elements.len() as u32)?;
+        write_u32(writer, rsbody.body_elements.first().map_or(0, Vec::len) as u32)?;
+        let mut bytes = vec![];
+        for row in &rsbody.body_elements {
+            for element in row {
+                match element {
+                    Some(cell) => {
+                        bytes.push(1);
+                        encode_cell(cell, &mut bytes);
+                    }
+                    None => bytes.push(0),
+                }
+            }
+        }
+        writer.write_all(&bytes)?;
+    }
+
+    write_support(writer, &physics_manager.support)
+}
+
+// INFO: Replaces the whole world with the one in the save, nothing is changed if the save can not be read
+pub fn load_world(physics_manager: &mut PhysicsManager, reader: &mut impl Read) -> io::Result<()> {
+    let version = read_header(reader, SAVE_MAGIC)?;
+
+    let seed = read_u64(reader)?;
+    let chunk_frame_count = read_u64(reader)? as u128;
+    if version == 1 {
+        // INFO: The chunk ranges, the loaded chunks are known from the chunks themselves now
+        for _ in 0..4 {
+            read_i32(reader)?;
+        }
+    }
+    let saved_bounds = if version >= 11 && read_u8(reader)? == 1 {
+        Some(((read_i32(reader)?, read_i32(reader)?), (read_i32(reader)?, read_i32(reader)?)))
+    } else {
+        None
+    };
+    if saved_bounds.is_some_and(|(min, max)| min.0 > max.0 || min.1 > max.1) {
+        return Err(invalid_data("World bounds are empty"));
+    }
+
+    let format = CellFormat { version, element_table: read_element_table(reader)? };
+
+    let mut chunks = vec![];
+    for _ in 0..read_u32(reader)? {
+        chunks.push(read_chunk(reader, &format)?);
+    }
+
+    // INFO: Empty chunks are not saved, older saves only know the bounds of the chunks that were saved
+    let world_bounds = saved_bounds.or_else(|| {
+        chunks.iter().map(|chunk| (chunk.index, chunk.index)).reduce(|(min, max), (index, _)| {
+            ((min.0.min(index.0), min.1.min(index.1)), (max.0.max(index.0), max.1.max(index.1)))
+        })
+    });
+
+    // INFO: Bodies were lifted out of the terrain, so they can not be bigger than the world
+    let (max_width, max_height) = match world_bounds {
+        Some((min, max)) => ((max.0 as i64 - min.0 as i64 + 1) as usize * COLS, (max.1 as i64 - min.1 as i64 + 1) as usize * ROWS),
+        None => (0, 0),
+    };
+
+    let mut bodies = vec![];
+    for _ in 0..read_u32(reader)? {
+        let mut transform = [0.; 6];
+        for value in transform.iter_mut() {
+            *value = read_f32(reader)?;
+        }
+
+        let (width, height) = (read_u32(reader)? as usize, read_u32(reader)? as usize);
+        if width == 0 || height == 0 || width > max_width || height > max_height {
+            return Err(invalid_data("Rigid sand body size does not fit into the world"));
+        }
+        // INFO: Row by row, a broken file runs out before much is allocated
+        let mut body_elements = vec![];
+        for _ in 0..width {
+            let mut row = vec![None; height];
+            for element in row.iter_mut() {
+                if read_u8(reader)? == 1 {
+                    *element = Some(decode_cell(reader, &format)?);
+                }
+            }
+            body_elements.push(row);
+        }
+        if body_elements.iter().flatten().all(Option::is_none) {
+            return Err(invalid_data("Rigid sand body has no elements"));
+        }
+        bodies.push((transform, body_elements));
+    }
+
+    let support = if version >= 9 { read_support(reader, chunks.len())? } else { SupportCheck::new() };
+
+    let chunk_manager = &mut physics_manager.chunk_manager;
+    chunk_manager.seed = seed;
+    chunk_manager.chunk_frame_count = chunk_frame_count;
+    chunk_manager.clear_chunks();
+
+    let rapier_handler = &mut physics_manager.rapier_handler;
+    rapier_handler.clear_world();
+
+    // INFO: Colliders are not saved, they are generated again from the loaded cells
+    for mut chunk in chunks {
+        chunk::update_bytes(&mut chunk);
+        chunk.colliders_dirty = true;
+        edges_from_chunk(&mut chunk);
+        chunk.last_needed = chunk_frame_count;
+        chunk_manager.insert_chunk(chunk);
+    }
+    chunk_manager.world_bounds = world_bounds;
+    rapier_handler.create_chunk_colliders(&mut chunk_manager.chunks);
+
+    for (transform, body_elements) in bodies {
+        let rsbody = RigidSandBody::from_elements(transform[0], transform[1], body_elements, &mut rapier_handler.rigid_body_set, &mut rapier_handler.collider_set);
+
+        let body = &mut rapier_handler.rigid_body_set[rsbody.rigid_body_handle];
+        body.set_rotation(Rotation::new(transform[2]), true);
+        body.set_linvel(vector![transform[3], transform[4]], true);
+        body.set_angvel(transform[5], true);
+
+        rapier_handler.rigid_sand_bodies.push(rsbody);
+    }
+    physics_manager.support = support;
+
+    Ok(())
+}
+
+pub fn write_chunk_file(chunk: &Chunk, path: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(CHUNK_MAGIC)?;
+    write_u32(&mut writer, SAVE_VERSION)?;
+    write_element_table(&mut writer, registry())?;
+    write_chunk(&mut writer, chunk)?;
+    writer.flush()
+}
+
+// INFO: The chunk comes back the way it was written, its colliders still have to be regenerated
+pub fn read_chunk_file(path: &Path) -> io::Result<Chunk> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let version = read_header(&mut reader, CHUNK_MAGIC)?;
+    let format = CellFormat { version, element_table: read_element_table(&mut reader)? };
+    read_chunk(&mut reader, &format)
+}
+
+fn read_header(reader: &mut impl Read, expected_magic: &[u8; 4]) -> io::Result<u32> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != expected_magic {
+        return Err(invalid_data("Not a save file"));
+    }
+
+    let version = read_u32(reader)?;
+    if version == 0 || version > SAVE_VERSION {
+        return Err(invalid_data(&format!("Unsupported save version {}, this build reads up to {}", version, SAVE_VERSION)));
+    }
+    Ok(version)
+}
+
+// INFO: Everything needed to read the cells of a save, what a cell is made of depends on the version it was written with
+struct CellFormat {
+    version: u32,
+    element_table: Vec<Element>,
+}
+
+fn write_element_table(writer: &mut impl Write, registry: &ElementRegistry) -> io::Result<()> {
+    write_u32(writer, registry.elements().len() as u32)?;
+    for element in registry.elements() {
+        let name = &registry.get(element).name;
+        write_u32(writer, name.len() as u32)?;
+        writer.write_all(name.as_bytes())?;
+    }
+    Ok(())
+}
+
+/*
+    INFO: Elements are looked up by name, so a save made before elements were added or reordered still loads.
+    Elements that no longer exist turn into air
+*/
+fn read_element_table(reader: &mut impl Read) -> io::Result<Vec<Element>> {
+    let count = read_u32(reader)? as usize;
+    if count > MAX_ELEMENTS {
+        return Err(invalid_data("Element table is too long"));
+    }
+
+    let mut element_table = vec![];
+    for _ in 0..count {
+        let len = read_u32(reader)? as usize;
+        if len > MAX_NAME_LEN {
+            return Err(invalid_data("Element name is too long"));
+        }
+        let mut name = vec![0; len];
+        reader.read_exact(&mut name)?;
+        let name = String::from_utf8_lossy(&name);
+
+        element_table.push(registry().by_name(&name).unwrap_or(Element::Air));
+    }
+    Ok(element_table)
+}
+
+// INFO: The search carries on over frames, a loaded world has to carry on with the same one
+fn write_support(writer: &mut impl Write, support: &SupportCheck) -> io::Result<()> {
+    let mut checked: Vec<(i32, i32)> = support.checked.iter().copied().collect();
+    checked.sort();
+
+    write_cells(writer, &support.next_round.iter().copied().collect::<Vec<_>>())?;
+    write_cells(writer, &support.round.iter().copied().collect::<Vec<_>>())?;
+    write_u32(writer, support.cursor as u32)?;
+    write_cells(writer, &checked)?;
+    match &support.search {
+        Some(search) => {
+            writer.write_all(&[1])?;
+            write_cells(writer, &search.structure)?;
+            write_u32(writer, search.next as u32)
+        }
+        None => writer.write_all(&[0]),
+    }
+}
+
+// INFO: Every chunk and cell the check refers to is in one of the saved chunks
+fn read_support(reader: &mut impl Read, chunk_count: usize) -> io::Result<SupportCheck> {
+    let max_cells = chunk_count * COLS * ROWS;
+    let mut support = SupportCheck::new();
+    support.next_round = read_cells(reader, chunk_count)?.into_iter().collect();
+    support.round = read_cells(reader, chunk_count)?.into_iter().collect();
+    support.cursor = read_u32(reader)? as usize;
+    if support.cursor > COLS * ROWS {
+        return Err(invalid_data("Support check cursor is outside of the chunk"));
+    }
+    support.checked = read_cells(reader, max_cells)?.into_iter().collect();
+    if read_u8(reader)? == 1 {
+        let structure = read_cells(reader, max_cells)?;
+        let next = read_u32(reader)? as usize;
+        if next > structure.len() {
+            return Err(invalid_data("Support search is past its structure"));
+        }
+        support.search = Some(Search::new(structure, next));
+    }
+    Ok(support)
+}
+
+fn write_cells(writer: &mut impl Write, cells: &[(i32, i32)]) -> io::Result<()> {
+    write_u32(writer, cells.len() as u32)?;
+    for &(x, y) in cells {
+        write_i32(writer, x)?;
+        write_i32(writer, y)?;
+    }
+    Ok(())
+}
+
+fn read_cells(reader: &mut impl Read, max: usize) -> io::Result<Vec<(i32, i32)>> {
+    let count = read_u32(reader)? as usize;
+    if count > max {
+        return Err(invalid_data("More cells than the world has"));
+    }
+
+    let mut cells = vec![];
+    for _ in 0..count {
+        cells.push((read_i32(reader)?, read_i32(reader)?));
+    }
+    Ok(cells)
+}
+
+fn write_chunk(writer: &mut impl Write, chunk: &Chunk) -> io::Result<()> {
+    write_i32(writer, chunk.index.0)?;
+    write_i32(writer, chunk.index.1)?;
+
+    // INFO: The dirty rect decides which cells get updated, without it a loaded world would not continue the same way
+    writer.write_all(&[chunk.active as u8])?;
+    let rect = &chunk.dirty_rect;
+    for value in [rect.min_xy, rect.max_xy, rect.temp_min_xy, rect.temp_max_xy] {
+        write_u32(writer, value.0 as u32)?;
+        write_u32(writer, value.1 as u32)?;
+    }
+
+    // INFO: The pressure field is only stored if there is something in it, most chunks have still air
+    let field = &chunk.pressure;
+    writer.write_all(&[field.flowing as u8 | (field.pressurized as u8) << 1])?;
+    if field.flowing || field.pressurized {
+        for (pressure, velocity) in field.pressure.iter().zip(&field.velocity) {
+            write_f32(writer, *pressure)?;
+            write_f32(writer, velocity.x)?;
+            write_f32(writer, velocity.y)?;
+        }
+    }
+    writer.write_all(&[chunk.liquid_moved as u8])?;
+
+    write_chunk_cells(writer, chunk)
+}
+
+fn read_chunk(reader: &mut impl Read, format: &CellFormat) -> io::Result<Chunk> {
+    let index = (read_i32(reader)?, read_i32(reader)?);
+    let mut chunk = Chunk::new(index.0, index.1);
+
+    chunk.active = read_u8(reader)? == 1;
+    let rect = &mut chunk.dirty_rect;
+    for value in [&mut rect.min_xy, &mut rect.max_xy, &mut rect.temp_min_xy, &mut rect.temp_max_xy] {
+        *value = (read_u32(reader)? as usize, read_u32(reader)? as usize);
+        if value.0 >= COLS || value.1 >= ROWS {
+            return Err(invalid_data("Dirty rect is outside of the chunk"));
+        }
+    }
+
+    if format.version >= 4 {
+        let flags = read_u8(reader)?;
+        let field = &mut chunk.pressure;
+        field.flowing = flags & 1 == 1;
+        field.pressurized = flags & 2 == 2;
+        if field.flowing || field.pressurized {
+            for (pressure, velocity) in field.pressure.iter_mut().zip(field.velocity.iter_mut()) {
+                *pressure = read_f32(reader)?;
+                *velocity = Vec2::new(read_f32(reader)?, read_f32(reader)?);
+            }
+        }
+    }
+    // INFO: Older saves did not keep track, their liquids are leveled once to be sure
+    chunk.liquid_moved = format.version < 10 || read_u8(reader)? == 1;
+
+    read_chunk_cells(reader, &mut chunk, format)?;
+    Ok(chunk)
+}
+
+// INFO: Most of a chunk is made out of runs of the same cell (mostly air), so cells are stored as (run length, cell)
+fn write_chunk_cells(writer: &mut impl Write, chunk: &Chunk) -> io::Result<()> {
+    let mut bytes = vec![];
+    let mut run = (0u32, vec![]);
+    let mut encoded = vec![];
+
+    for column in chunk.grid.iter() {
+        for cell in column.iter() {
+            encoded.clear();
+            encode_cell(cell, &mut encoded);
+
+            if run.0 > 0 && encoded == run.1 {
+                run.0 += 1;
+            } else {
+                if run.0 > 0 {
+                    bytes.extend_from_slice(&run.0.to_le_bytes());
+                    bytes.extend_from_slice(&run.1);
+                }
+                run = (1, encoded.clone());
+            }
+        }
+    }
+    bytes.extend_from_slice(&run.0.to_le_bytes());
+    bytes.extend_from_slice(&run.1);
+
+    writer.write_all(&bytes)
+}
+
+fn read_chunk_cells(reader: &mut impl Read, chunk: &mut Chunk, format: &CellFormat) -> io::Result<()> {
+    let mut read = 0;
+    while read < COLS * ROWS {
+        let run = read_u32(reader)? as usize;
+        let cell = decode_cell(reader, format)?;
+        if run == 0 || read + run > COLS * ROWS {
+            return Err(invalid_data("Chunk cells do not fit into the chunk"));
+        }
+
+        for n in read..read + run {
+            chunk.grid[n / ROWS][n % ROWS] = cell;
+        }
+        read += run;
+    }
+    Ok(())
+}
+
+/*
+    INFO: Only the parts of a cell that change during the simulation are stored,
+    density, state and drag always come from the element itself
+*/
+fn encode_cell(cell: &Cell, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&element_id(cell.element).to_le_bytes());
+    match cell.action {
+        None => bytes.push(0),
+        Some(Action::EmitSource(element)) => {
+            bytes.push(2);
+            bytes.extend_from_slice(&element_id(element).to_le_bytes());
+        }
+        Some(Action::Grow(growth)) => {
+            bytes.push(3);
+            match growth {
+                Growth::Grass => bytes.push(0),
+                Growth::Seed => bytes.push(1),
+                Growth::Tree => bytes.push(2),
+                Growth::Branch(dx) => bytes.extend_from_slice(&[3, dx as u8]),
+                Growth::Vine => bytes.push(4),
+            }
+        }
+        Some(Action::Corrode) => bytes.push(4),
+        Some(Action::Power) => bytes.push(5),
+        Some(Action::Sense(element)) => {
+            bytes.push(6);
+            bytes.extend_from_slice(&element_id(element).to_le_bytes());
+        }
+        Some(Action::Gate(kind)) => {
+            bytes.push(7);
+            bytes.push(match kind {
+                LogicGate::And => 0,
+                LogicGate::Or => 1,
+                LogicGate::Not => 2,
+                LogicGate::Delay => 3,
+            });
+        }
+        Some(Action::Door(panel)) => {
+            bytes.push(8);
+            bytes.extend_from_slice(&element_id(panel).to_le_bytes());
+        }
+        Some(Action::Piston(arm)) => {
+            bytes.push(9);
+            bytes.extend_from_slice(&element_id(arm).to_le_bytes());
+        }
+        Some(Action::Creature(creature)) => {
+            bytes.push(10);
+            bytes.push(match creature.kind {
+                CreatureKind::Ant => 0,
+                CreatureKind::Worm => 1,
+            });
+            bytes.push(creature.facing as u8);
+            bytes.push(match creature.mode {
+                CreatureMode::Walk => 0,
+                CreatureMode::Fall => 1,
+                CreatureMode::Dig => 2,
+            });
+        }
+    }
+    bytes.extend_from_slice(&cell.velocity.x.to_le_bytes());
+    bytes.extend_from_slice(&cell.velocity.y.to_le_bytes());
+    bytes.extend_from_slice(&cell.lifetime.to_le_bytes());
+    bytes.extend_from_slice(&cell.color);
+    bytes.extend_from_slice(&cell.temperature.to_le_bytes());
+    bytes.push(cell.charge);
+    bytes.push(cell.wetness);
+    bytes.push(cell.free_falling as u8);
+    bytes.push(match cell.collider_type {
+        ElColliderType::None => 0,
+        ElColliderType::Chunk => 1,
+        ElColliderType::Body => 2,
+    });
+}
+
+fn decode_cell(reader: &mut impl Read, format: &CellFormat) -> io::Result<Cell> {
+    let mut cell = el_from_enum(read_element(reader, format)?);
+
+    cell.action = match read_u8(reader)? {
+        0 => None,
+        // INFO: Cells of older saves that were burning only keep their heat, burning is a reaction with fire now
+        1 => None,
+        2 => Some(Action::EmitSource(read_element(reader, format)?)),
+        // INFO: Before version 6 grass was the only thing that grew
+        3 if format.version < 6 => Some(Action::Grow(Growth::Grass)),
+        3 => Some(Action::Grow(match read_u8(reader)? {
+            0 => Growth::Grass,
+            1 => Growth::Seed,
+            2 => Growth::Tree,
+            3 => Growth::Branch(read_u8(reader)? as i8),
+            4 => Growth::Vine,
+            growth => return Err(invalid_data(&format!("Unknown growth {}", growth))),
+        })),
+        4 => Some(Action::Corrode),
+        5 => Some(Action::Power),
+        6 => Some(Action::Sense(read_element(reader, format)?)),
+        7 => Some(Action::Gate(match read_u8(reader)? {
+            0 => LogicGate::And,
+            1 => LogicGate::Or,
+            2 => LogicGate::Not,
+            3 => LogicGate::Delay,
+            kind => return Err(invalid_data(&format!("Unknown logic gate {}", kind))),
+        })),
+        8 => Some(Action::Door(read_element(reader, format)?)),
+        9 => Some(Action::Piston(read_element(reader, format)?)),
+        10 => Some(Action::Creature(Creature {
+            kind: match read_u8(reader)? {
+                0 => CreatureKind::Ant,
+                1 => CreatureKind::Worm,
+                kind => return Err(invalid_data(&format!("Unknown creature {}", kind))),
+            },
+            facing: read_u8(reader)? as i8,
+            mode: match read_u8(reader)? {
+                0 => CreatureMode::Walk,
+                1 => CreatureMode::Fall,
+                2 => CreatureMode::Dig,
+                mode => return Err(invalid_data(&format!("Unknown creature mode {}", mode))),
+            },
+        })),
+        action => return Err(invalid_data(&format!("Unknown action {}", action))),
+    };
+    cell.velocity = Vec2::new(read_f32(reader)?, read_f32(reader)?);
+    cell.lifetime = read_i32(reader)?;
+    reader.read_exact(&mut cell.color)?;
+    if format.version >= 3 {
+        cell.temperature = read_f32(reader)?;
+    }
+    if format.version >= 5 {
+        cell.charge = read_u8(reader)?;
+    }
+    if format.version >= 7 {
+        cell.wetness = read_u8(reader)?;
+    }
+    if format.version >= 8 {
+        cell.free_falling = read_u8(reader)? != 0;
+    }
+    cell.collider_type = match read_u8(reader)? {
+        0 => ElColliderType::None,
+        1 => ElColliderType::Chunk,
+        2 => ElColliderType::Body,
+        collider_type => return Err(invalid_data(&format!("Unknown collider type {}", collider_type))),
+    };
+
+    Ok(cell)
+}
+
+fn element_id(element: Element) -> u16 {
+    element.0
+}
+
+fn read_element(reader: &mut impl Read, format: &CellFormat) -> io::Result<Element> {
+    let id = read_u16(reader)? as usize;
+    format
+        .element_table
+        .get(id)
+        .copied()
+        .ok_or_else(|| invalid_data(&format!("Element {} is not in the element table", id)))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_u32(writer: &mut impl Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_i32(writer: &mut impl Write, value: i32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_f32(writer: &mut impl Write, value: f32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    Ok(read_bytes::<1>(reader)?[0])
+}
+
+fn read_u16(reader: &mut impl Read) -> io::Result<u16> {
+    Ok(u16::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    Ok(u32::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    Ok(u64::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_i32(reader: &mut impl Read) -> io::Result<i32> {
+    Ok(i32::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    Ok(f32::from_le_bytes(read_bytes(reader)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::phys_world::{
+        all_physics_manager::PhysicsManager,
+        chunk_manager::EditInput,
+        element::Element,
+        element_registry::{registry, ElementRegistry},
+        scenario::Scenario,
+    };
+
+    use std::io;
+
+    use super::{element_id, load_world, read_element, read_element_table, save_world, write_element_table, CellFormat, SAVE_VERSION};
+
+    const DEFINITIONS: &str = include_str!("../assets/elements.ron");
+
+    fn run(physics_manager: &mut PhysicsManager, ticks: u32) {
+        for _ in 0..ticks {
+            physics_manager.step(&EditInput::default());
+            physics_manager.clear_body_bytes();
+        }
+    }
+
+    #[test]
+    fn loaded_world_carries_on_like_a_straight_run() {
+        for scenario in [Scenario::Bonfire, Scenario::WaterTank, Scenario::SandPile] {
+            let mut straight = PhysicsManager::new();
+            straight.chunk_manager.seed = 3;
+            scenario.build(&mut straight.chunk_manager);
+            run(&mut straight, 60);
+
+            let mut saved = PhysicsManager::new();
+            saved.chunk_manager.seed = 3;
+            scenario.build(&mut saved.chunk_manager);
+            run(&mut saved, 30);
+            let mut bytes = vec![];
+            save_world(&saved, &mut bytes).unwrap();
+
+            let mut loaded = PhysicsManager::new();
+            load_world(&mut loaded, &mut bytes.as_slice()).unwrap();
+            run(&mut loaded, 30);
+
+            assert_eq!(straight.chunk_manager.world_checksum(), loaded.chunk_manager.world_checksum(), "{}", scenario.name());
+        }
+    }
+
+    // INFO: A save made with an element the current definitions do not have, listed first so every element after it had another id
+    #[test]
+    fn elements_are_found_by_name_after_the_definitions_changed() {
+        let extended = DEFINITIONS.replacen(
+            "    elements: [\n",
+            "    elements: [\n        (name: \"Glowstone\", state: Solid, density: 100.0, drag: 0.0, color: (250, 240, 120, 255)),\n",
+            1,
+        );
+        let old = ElementRegistry::from_ron(&extended, None).unwrap();
+
+        let mut bytes = vec![];
+        write_element_table(&mut bytes, &old).unwrap();
+        let format = CellFormat { version: SAVE_VERSION, element_table: read_element_table(&mut bytes.as_slice()).unwrap() };
+
+        for name in ["Air", "Water", "Lava", "Acid", "Honey", "WetSand", "Glowstone"] {
+            let id = element_id(old.by_name(name).unwrap());
+            let element = read_element(&mut id.to_le_bytes().as_slice(), &format).unwrap();
+            assert_eq!(element, registry().by_name(name).unwrap_or(Element::Air), "{}", name);
+        }
+        assert_ne!(old.by_name("Acid"), registry().by_name("Acid"));
+    }
+    #[test]
+    fn too_long_element_name_is_invalid_data() {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        let error = read_element_table(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,208 @@
+use ahash::RandomState;
+use std::{collections::HashMap, time::Instant};
+
+use glam::{Mat3, Vec2};
+use notan::{draw::*, prelude::*};
+use perftest::phys_world::{
+    all_physics_manager::PhysicsManager,
+    chunk::{self, Chunk, COLS, ROWS, UPSCALE_FACTOR},
+    chunk_manager::ChunkManager,
+    rapier_world_handler::{RapierHandler, PHYS_SCALE},
+    rigid_sand_body::RigidSandBody,
+};
+use rapier2d::prelude::RigidBodySet;
+
+use crate::debug_ui::DebugInfo;
+
+// INFO: The rendering layer, it only consumes the chunk bytes that the simulation writes
+pub struct WorldRenderer {
+    chunk_textures: HashMap<(i32, i32), Texture, RandomState>,
+    font: Font,
+}
+
+impl WorldRenderer {
+    pub fn new(gfx: &mut Graphics) -> Self {
+        Self {
+            chunk_textures: HashMap::default(),
+            font: gfx
+                .create_font(include_bytes!("assets/UbuntuMono.ttf"))
+                .unwrap(),
+        }
+    }
+
+    pub fn render(&mut self, gfx: &mut Graphics, draw: &mut Draw, physics_manager: &mut PhysicsManager, debug_info: &DebugInfo) {
+        self.render_chunks(gfx, draw, &mut physics_manager.chunk_manager);
+
+        physics_manager.clear_body_bytes();
+
+        self.debug_render_chunks(draw, &physics_manager.chunk_manager, debug_info);
+        debug_render_rapier(draw, &physics_manager.rapier_handler, debug_info);
+    }
+
+    fn render_chunks(&mut self, gfx: &mut Graphics, draw: &mut Draw, chunk_manager: &mut ChunkManager) {
+        let now = Instant::now();
+
+        // INFO: Textures of chunks that were unloaded are dropped, they get created again if the chunk comes back
+        self.chunk_textures.retain(|index, _| chunk_manager.chunks.contains_key(index));
+
+        for chunk in chunk_manager.chunks.values_mut() {
+            let texture = self
+                .chunk_textures
+                .entry(chunk.index)
+                .or_insert_with(|| create_chunk_texture(gfx, &chunk.bytes));
+
+            update_chunk_tex_data(chunk, texture, gfx, chunk_manager.update_chunks);
+
+            draw.image(texture)
+                .size(COLS as f32 * UPSCALE_FACTOR, ROWS as f32 * UPSCALE_FACTOR)
+                .position(chunk.pos.0, chunk.pos.1);
+        }
+        chunk_manager.chunks_render_time = now.elapsed();
+    }
+
+    fn debug_render_chunks(&self, draw: &mut Draw, chunk_manager: &ChunkManager, debug_info: &DebugInfo) {
+        if debug_info.debug_chunk_coords {
+            for index in chunk_manager.chunks.keys() {
+                draw.text(&self.font, &format!("{}, {}", index.0, index.1))
+                    .position(
+                        (COLS as f32 / 2. + (index.0 as f32 * COLS as f32)) * UPSCALE_FACTOR,
+                        (ROWS as f32 / 2. + (index.1 as f32 * ROWS as f32)) * UPSCALE_FACTOR,
+                    )
+                    .h_align_center()
+                    .v_align_middle();
+            }
+        }
+
+        if debug_info.debug_chunk_bounds {
+            for (index, chunk) in chunk_manager.chunks.iter() {
+                draw.rect(
+                    (
+                        index.0 as f32 * COLS as f32 * UPSCALE_FACTOR + 0.5,
+                        index.1 as f32 * ROWS as f32 * UPSCALE_FACTOR + 0.5,
+                    ),
+                    (
+                        COLS as f32 * UPSCALE_FACTOR - 1.,
+                        ROWS as f32 * UPSCALE_FACTOR - 1.,
+                    ),
+                )
+                .fill_color(Color::from_rgba(0., 0., 0., 0.))
+                .stroke_color(if chunk.active {
+                    Color::GREEN
+                } else {
+                    Color::RED
+                })
+                .stroke(1.);
+            }
+        }
+
+        if debug_info.debug_dirty_rects {
+            for (index, chunk) in chunk_manager.chunks.iter() {
+                if chunk.active {
+                    draw.rect(
+                        (
+                            (index.0 as f32 * COLS as f32 + chunk.dirty_rect.min_xy.0 as f32)
+                                * UPSCALE_FACTOR,
+                            (index.1 as f32 * ROWS as f32 + chunk.dirty_rect.min_xy.1 as f32)
+                                * UPSCALE_FACTOR,
+                        ),
+                        (
+                            (chunk.dirty_rect.max_xy.0 - chunk.dirty_rect.min_xy.0) as f32
+                                * UPSCALE_FACTOR,
+                            (chunk.dirty_rect.max_xy.1 - chunk.dirty_rect.min_xy.1) as f32
+                                * UPSCALE_FACTOR,
+                        ),
+                    )
+                    .fill_color(Color::from_rgba(0., 0., 0., 0.))
+                    .stroke_color(Color::BLUE)
+                    .stroke(1.);
+                }
+            }
+        }
+
+        if debug_info.debug_chunk_edges {
+            for (index, chunk) in chunk_manager.chunks.iter() {
+                if !chunk.edges.is_empty() {
+                    let mut i = 0;
+                    for line in chunk.edges.iter() {
+                        if !line.is_empty() {
+                            let mut prev_point = &line[0];
+                            for point in line {
+                                draw.line(
+                                    ((index.0 as f32 * COLS as f32 / (PHYS_SCALE / UPSCALE_FACTOR) + prev_point.x) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR,
+                                     (index.1 as f32 * ROWS as f32 / (PHYS_SCALE / UPSCALE_FACTOR) + prev_point.y) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR),
+                                    ((index.0 as f32 * COLS as f32 / (PHYS_SCALE / UPSCALE_FACTOR) + point.x) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR,
+                                     (index.1 as f32 * ROWS as f32 / (PHYS_SCALE / UPSCALE_FACTOR) + point.y) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR)
+                                ).color(Color::from_bytes(i * 100 + 150, i * 25, i * 50 + 150, 255));
+                                prev_point = point;
+                            }
+                            i += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn create_chunk_texture(gfx: &mut Graphics, bytes: &[u8]) -> Texture {
+    gfx.create_texture()
+        .from_bytes(bytes, COLS as u32, ROWS as u32)
+        .with_filter(TextureFilter::Nearest, TextureFilter::Nearest)
+        .build()
+        .unwrap()
+}
+
+fn update_chunk_tex_data(chunk: &mut Chunk, texture: &mut Texture, gfx: &mut Graphics, update_chunks: bool) {
+    if chunk.dirty_tex {
+        /*
+            INFO: Texture data is updated at the same time as movements are done
+            we need to only manualy update the texture data when the chunks are paused
+        */
+        if !update_chunks {
+            chunk::update_bytes(chunk);
+        }
+
+        gfx.update_texture(texture)
+            .with_data(&chunk.bytes)
+            .update()
+            .unwrap();
+
+        chunk.dirty_tex = false;
+    }
+}
+
+fn debug_render_rapier(draw: &mut Draw, rapier_handler: &RapierHandler, debug_info: &DebugInfo) {
+    for ball_body_handle in &rapier_handler.ball_body_handles {
+        let ball_body = &rapier_handler.rigid_body_set[*ball_body_handle];
+        let pos = ball_body.translation();
+        draw.circle(8.).translate(pos.x * PHYS_SCALE, pos.y * PHYS_SCALE);
+    }
+
+    if debug_info.debug_rs_body_edges {
+        for rsbody in &rapier_handler.rigid_sand_bodies {
+            debug_render_rsbody(draw, rsbody, &rapier_handler.rigid_body_set);
+        }
+    }
+}
+
+fn debug_render_rsbody(draw: &mut Draw, rsbody: &RigidSandBody, rigid_body_set: &RigidBodySet) {
+    let body = &rigid_body_set[rsbody.rigid_body_handle];
+
+    let translation = Mat3::from_translation(Vec2::new(body.translation().x * PHYS_SCALE, body.translation().y * PHYS_SCALE));
+    let rotation = Mat3::from_angle(body.rotation().angle());
+    let matrix = translation * rotation;
+
+    draw.transform().push(matrix);
+
+    let mut prev_point = &rsbody.body_edge[0];
+    for point in &rsbody.body_edge {
+        draw.line(
+            ((prev_point.x) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR,
+             (prev_point.y) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR),
+            ((point.x) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR,
+             (point.y) * UPSCALE_FACTOR * PHYS_SCALE / UPSCALE_FACTOR)
+        ).color(Color::MAGENTA);
+        prev_point = point;
+    }
+    draw.transform().pop();
+}